@@ -0,0 +1,22 @@
+// External includes.
+
+// Standard includes.
+
+// Internal includes.
+
+/// The shape of a walkable tile of a corridor, by which of its 4-connected neighbours are also walkable, as found by [`SparseMap::corridor_topology`](struct.SparseMap.html#method.corridor_topology).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CorridorKind {
+    /// No neighbour is walkable.
+    Isolated,
+    /// One neighbour is walkable; the corridor ends here.
+    DeadEnd,
+    /// Two opposite neighbours are walkable; the corridor runs straight through.
+    Straight,
+    /// Two adjacent neighbours are walkable; the corridor turns here.
+    Corner,
+    /// Three neighbours are walkable; a side passage branches off here.
+    TJunction,
+    /// All four neighbours are walkable; two corridors cross here.
+    Crossing,
+}
@@ -0,0 +1,599 @@
+// External includes.
+use super::{
+    get_new_map_id, register_map, Map, MapId, Portal, PortalCollection, Portals, PortalsMut,
+    SubMap, SubMapCollection, SubMaps, SubMapsMut, TileType, TileTypeCmp, TileTypeStandardCmp,
+    MAPS,
+};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+use super::for_each_resolved_sub_map;
+use super::PortalKind;
+use super::SparseMap;
+use super::{rotate_sub_maps, rotation_adjustment};
+
+/// A map which stores its [`TileType`](enum.TileType.html) information in a `Vec`, in rows, indexed by the local [`Position`](geometry/struct.Position.html).
+///
+/// Every local `Position` within the `DenseMap`'s area holds a tile; positions which have not been set hold `TileType::Void`. As `TileTypeStandardCmp` ranks `None` above `TileType::Void`, these read as `None`, exactly as missing tiles in a [`SparseMap`](struct.SparseMap.html) do. Local `Position`s outside of the area also read as `None`.
+///
+/// The size of the `DenseMap` will expand based on the `Position` provided, as per the specification for [`Map`](trait.Map.html), by reallocating its backing `Vec`; the `Vec` grows to at least twice its width or height at a time, so that setting tiles one by one, as the area grows, does not copy every tile each time. Negative local `Position`s cannot be stored, and are ignored, as in a `SparseMap`: nothing is stored, and nothing is written through to sub-maps.
+#[derive(Clone)]
+pub struct DenseMap {
+    map_id: MapId,
+    area: Area,
+    tiles: Vec<TileType>,
+    stride: usize,
+    portals: Vec<Portal>,
+    sub_maps: Vec<SubMap>,
+}
+
+impl DenseMap {
+    fn index_of(&self, pos: Position) -> Option<usize> {
+        if !self.size().intersects_local_position(pos) || pos.x() as usize >= self.stride {
+            return None;
+        }
+
+        let index = pos.y() as usize * self.stride + pos.x() as usize;
+        if index < self.tiles.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn from_parts(
+        map_id: MapId,
+        area: Area,
+        tiles: Vec<TileType>,
+        portals: Vec<Portal>,
+        sub_maps: Vec<SubMap>,
+    ) -> Self {
+        Self {
+            map_id,
+            area,
+            tiles,
+            stride: area.width() as usize,
+            portals,
+            sub_maps,
+        }
+    }
+
+    /// Creates a new `DenseMap`. As `DenseMap` expands to meet its use, no parameters need be supplied.
+    ///
+    /// `DenseMap::default()` creates the same map, with a fresh `MapId`, without registering it.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> MapId {
+        register_map(Self::default())
+    }
+
+    /// Creates and registers a new [`SparseMap`](struct.SparseMap.html) with the same area, tiles, `Portal`s, and sub-maps as this `DenseMap`, and returns its `MapId`.
+    ///
+    /// Only tiles which are not `TileType::Void` are stored in the new `SparseMap`, so a `DenseMap` which is mostly `Void` converts to a small `SparseMap`; either way, every local `Position` reads the same from both.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut dense_map = DenseMap::default();
+    /// for y in 0..4 {
+    ///     for x in 0..5 {
+    ///         let tile_type = if (x + y) % 2 == 0 { TileType::Floor } else { TileType::Wall };
+    ///         dense_map.tile_type_at_local_set(Position::new(x, y), tile_type);
+    ///     }
+    /// }
+    /// dense_map.tile_type_at_local_set(Position::new(7, 5), TileType::Void);
+    /// dense_map.add_portal(Position::new(0, 0), CardinalDirection::West, Position::zero(), target);
+    ///
+    /// let map_id = dense_map.to_sparse();
+    /// let maps = MAPS.read();
+    /// let sparse_map = maps[map_id].read();
+    /// assert!(*sparse_map.area() == *dense_map.area());
+    /// assert!(sparse_map.portal_count() == 1);
+    /// for y in -1..=dense_map.height() as i32 {
+    ///     for x in -1..=dense_map.width() as i32 {
+    ///         let position = Position::new(x, y);
+    ///         assert!(sparse_map.tile_type_at_local(position) == dense_map.tile_type_at_local(position));
+    ///     }
+    /// }
+    /// ```
+    pub fn to_sparse(&self) -> MapId {
+        let self_position = *self.position();
+        let tiles = (0..self.height() as i32)
+            .flat_map(|y| (0..self.width() as i32).map(move |x| Position::new(x, y)))
+            .filter_map(|position| {
+                let tile_type = self.tiles[self.index_of(position)?];
+                if tile_type == TileType::Void {
+                    None
+                } else {
+                    Some((position + self_position, tile_type))
+                }
+            })
+            .collect();
+
+        register_map(SparseMap::from_parts(
+            get_new_map_id(),
+            self.area,
+            tiles,
+            self.portals
+                .iter()
+                .map(|portal| (portal.clone(), PortalKind::default()))
+                .collect(),
+            self.sub_maps.clone(),
+        ))
+    }
+
+    /// Creates a new `DenseMap` covering the given `Area`, with every tile preallocated as `TileType::Void`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = DenseMap::with_area(Area::new(Position::zero(), Size::new(3, 2)));
+    ///
+    /// let maps = MAPS.read();
+    /// let dense_map = maps[map_id].read();
+    /// assert!(*dense_map.size() == Size::new(3, 2));
+    /// assert!(dense_map.tile_type_at_local(Position::new(2, 1)) == None);
+    /// assert!(!dense_map.intersects_local_position(Position::new(2, 1)));
+    /// ```
+    pub fn with_area(area: Area) -> MapId {
+        register_map(Self {
+            area,
+            tiles: vec![TileType::Void; area.width() as usize * area.height() as usize],
+            stride: area.width() as usize,
+            ..Self::default()
+        })
+    }
+
+    fn resize(&mut self, size: Size) {
+        if size == *self.size() {
+            return;
+        }
+
+        let width = size.width() as usize;
+        let height = size.height() as usize;
+        let rows = self.tiles.len().checked_div(self.stride).unwrap_or(0);
+        if width <= self.stride && height <= rows {
+            // The tiles fit as they are; clear those left outside, so that they do not return if the area grows again.
+            for y in 0..rows {
+                let start = y * self.stride;
+                let keep = if y < height { width } else { 0 };
+                self.tiles[start + keep..start + self.stride].fill(TileType::Void);
+            }
+        } else {
+            let new_stride = if width > self.stride {
+                width.max(self.stride * 2)
+            } else {
+                self.stride
+            };
+            let new_rows = if height > rows {
+                height.max(rows * 2)
+            } else {
+                rows
+            };
+            let mut new_tiles = vec![TileType::Void; new_stride * new_rows];
+            let copy_width = (self.width() as usize).min(width);
+            for y in 0..(self.height() as usize).min(height) {
+                let old_start = y * self.stride;
+                let new_start = y * new_stride;
+                new_tiles[new_start..new_start + copy_width]
+                    .copy_from_slice(&self.tiles[old_start..old_start + copy_width]);
+            }
+
+            self.tiles = new_tiles;
+            self.stride = new_stride;
+        }
+
+        *self.size_mut() = size;
+    }
+}
+
+impl ContainsLocalPosition for DenseMap {
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let map_id = DenseMap::new();
+    ///
+    /// # let maps = MAPS.read();
+    /// # let mut dense_map = maps[map_id].write();
+    /// for y in -1..=1 {
+    ///     for x in -1..=1 {
+    ///         let position = Position::new(2, 2) + Position::new(x, y);
+    ///         dense_map.tile_type_at_local_set(position, TileType::Wall);
+    ///     }
+    /// }
+    ///
+    /// for y in -2..=2 {
+    ///     for x in -2..=2 {
+    ///         let position = Position::new(2, 2) + Position::new(x, y);
+    ///         let containment = dense_map.contains_local_position(position);
+    ///         if x.abs() == 2 || y.abs() == 2 {
+    ///             assert!(containment == Containment::Disjoint);
+    ///         } else if x.abs() == 1 || y.abs() == 1 {
+    ///             assert!(containment == Containment::Intersects);
+    ///         } else {
+    ///             assert!(containment == Containment::Contains);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn contains_local_position(&self, position: Position) -> Containment {
+        if self.intersects_local_position(position) {
+            if self.intersects_local_position(position + Position::NORTH)
+                && self.intersects_local_position(position + Position::NORTH + Position::EAST)
+                && self.intersects_local_position(position + Position::EAST)
+                && self.intersects_local_position(position + Position::SOUTH + Position::EAST)
+                && self.intersects_local_position(position + Position::SOUTH)
+                && self.intersects_local_position(position + Position::SOUTH + Position::WEST)
+                && self.intersects_local_position(position + Position::WEST)
+                && self.intersects_local_position(position + Position::NORTH + Position::WEST)
+            {
+                Containment::Contains
+            } else {
+                Containment::Intersects
+            }
+        } else {
+            Containment::Disjoint
+        }
+    }
+}
+
+impl ContainsPosition for DenseMap {}
+
+impl Default for DenseMap {
+    /// Creates a new, empty `DenseMap` with a fresh `MapId`.
+    ///
+    /// The `DenseMap` is not registered; pass it to `register_map` to make it available through `MAPS`.
+    fn default() -> Self {
+        Self {
+            map_id: get_new_map_id(),
+            area: Area::new(Position::zero(), Size::zero()),
+            tiles: Vec::new(),
+            stride: 0,
+            portals: Vec::new(),
+            sub_maps: Vec::new(),
+        }
+    }
+}
+
+impl HasArea for DenseMap {
+    fn area(&self) -> &Area {
+        &self.area
+    }
+
+    fn area_mut(&mut self) -> &mut Area {
+        &mut self.area
+    }
+}
+
+impl HasHeight for DenseMap {
+    fn height(&self) -> Length {
+        self.size().height()
+    }
+
+    fn height_mut(&mut self) -> &mut Length {
+        self.size_mut().height_mut()
+    }
+}
+
+impl HasPosition for DenseMap {
+    fn position(&self) -> &Position {
+        self.area.position()
+    }
+
+    fn position_mut(&mut self) -> &mut Position {
+        self.area.position_mut()
+    }
+}
+
+impl HasSize for DenseMap {
+    fn size(&self) -> &Size {
+        self.area.size()
+    }
+
+    fn size_mut(&mut self) -> &mut Size {
+        self.area.size_mut()
+    }
+}
+
+impl HasWidth for DenseMap {
+    fn width(&self) -> Length {
+        self.size().width()
+    }
+
+    fn width_mut(&mut self) -> &mut Length {
+        self.size_mut().width_mut()
+    }
+}
+
+impl IntersectsLocalPosition for DenseMap {
+    fn intersects_local_position(&self, position: Position) -> bool {
+        self.area().intersects_local_position(position)
+            && !matches!(
+                self.tile_type_at_local(position),
+                Some(TileType::Void) | None
+            )
+    }
+}
+
+impl IntersectsPosition for DenseMap {}
+
+impl Map for DenseMap {
+    fn box_clone(&self) -> Box<dyn Map> {
+        Box::new((*self).clone())
+    }
+
+    fn map_id(&self) -> MapId {
+        self.map_id
+    }
+
+    fn rotate(&mut self, rotation: CardinalRotation) {
+        let adjust_position = rotation_adjustment(*self.size(), rotation);
+        let new_size = *self.size() * rotation;
+
+        for portal_mut in self.portals.iter_mut() {
+            *portal_mut.local_position_mut() =
+                adjust_position + *portal_mut.local_position() * rotation;
+        }
+
+        let mut new_tiles =
+            vec![TileType::Void; new_size.width() as usize * new_size.height() as usize];
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let new_position = adjust_position + Position::new(x, y) * rotation;
+                new_tiles[new_position.y() as usize * new_size.width() as usize
+                    + new_position.x() as usize] =
+                    self.tiles[y as usize * self.stride + x as usize];
+            }
+        }
+
+        let self_position = *self.position();
+        rotate_sub_maps(
+            &mut self.sub_maps,
+            self_position,
+            self_position * rotation,
+            adjust_position,
+            rotation,
+        );
+
+        self.tiles = new_tiles;
+        self.stride = new_size.width() as usize;
+        *self.position_mut() = *self.position() * rotation;
+        *self.size_mut() = new_size;
+    }
+
+    fn tile_type_at_local(&self, pos: Position) -> Option<TileType> {
+        let mut output = None;
+        for_each_resolved_sub_map(self.map_id, &self.sub_maps, |sub_map, map| {
+            let sub_map_position = *sub_map.local_position();
+            let local_position = pos - sub_map_position + *self.position();
+            let test = map.tile_type_at_local(local_position);
+            output = *TileTypeStandardCmp::return_greater_option(&output, &test);
+        });
+
+        let self_tile_type = self.index_of(pos).map(|index| self.tiles[index]);
+        output = *TileTypeStandardCmp::return_greater_option(&output, &self_tile_type);
+
+        output
+    }
+
+    fn tile_type_at_local_mut(&mut self, pos: Position) -> Option<&mut TileType> {
+        let index = self.index_of(pos)?;
+        self.tiles.get_mut(index)
+    }
+
+    fn tile_type_at_local_set(&mut self, pos: Position, tile_type: TileType) -> Option<TileType> {
+        if pos.x() < 0 || pos.y() < 0 {
+            return None;
+        }
+
+        if !self.sub_maps.is_empty() {
+            let maps = MAPS.read();
+            for sub_map in self.sub_maps.iter() {
+                let mut map = maps[sub_map.value()].write();
+                let sub_map_position = *sub_map.local_position();
+                let local_position = pos - sub_map_position + *self.position();
+                if map.is_local_position_valid(local_position) {
+                    map.tile_type_at_local_set(local_position, tile_type);
+                }
+            }
+        }
+
+        let new_size = Size::new(
+            self.width().max(pos.x() as u32 + 1),
+            self.height().max(pos.y() as u32 + 1),
+        );
+        self.resize(new_size);
+
+        let index = self.index_of(pos)?;
+        Some(std::mem::replace(&mut self.tiles[index], tile_type))
+    }
+
+    /// Gets an option for an immutable reference to the `TileType` at the given local `Position`. Returns None if the local `Position` is out of bounds, or there is no tile at that location.
+    ///
+    /// Uses a comparison function to determine which sub-map tile has priority, if any.
+    fn tile_type_at_local_sort_by<'a>(
+        &self,
+        pos: Position,
+        sort_best: &dyn Fn(&Option<TileType>, &Option<TileType>) -> std::cmp::Ordering,
+    ) -> Option<TileType> {
+        let mut output = None;
+        for_each_resolved_sub_map(self.map_id, &self.sub_maps, |sub_map, map| {
+            let sub_map_position = *sub_map.local_position();
+            let local_position = pos - sub_map_position + *self.position();
+            let test = map.tile_type_at_local(local_position);
+            output = match sort_best(&output, &test) {
+                std::cmp::Ordering::Greater => output,
+                std::cmp::Ordering::Equal => output,
+                std::cmp::Ordering::Less => test,
+            };
+        });
+
+        let self_tile_type = self.index_of(pos).map(|index| self.tiles[index]);
+        output = match sort_best(&output, &self_tile_type) {
+            std::cmp::Ordering::Greater => output,
+            std::cmp::Ordering::Equal => output,
+            std::cmp::Ordering::Less => self_tile_type,
+        };
+
+        output
+    }
+}
+
+impl Placed for DenseMap {}
+
+impl PlacedObject for DenseMap {}
+
+impl PortalCollection for DenseMap {
+    fn add_portal(
+        &mut self,
+        local_position: Position,
+        portal_to_map_facing: CardinalDirection,
+        portal_to_map_position: Position,
+        target: MapId,
+    ) {
+        self.portals.push(Portal::new(
+            local_position,
+            portal_to_map_facing,
+            portal_to_map_position,
+            target,
+        ));
+        self.tile_type_at_local_set(local_position, TileType::Portal);
+    }
+
+    fn get_portal_at(&self, index: usize) -> Option<&Portal> {
+        self.portals.get(index)
+    }
+
+    fn get_portal_at_mut(&mut self, index: usize) -> Option<&mut Portal> {
+        self.portals.get_mut(index)
+    }
+
+    fn portal_count(&self) -> usize {
+        self.portals.len()
+    }
+
+    fn portals(&self) -> Portals<'_> {
+        Portals::new(&self.portals)
+    }
+
+    fn portals_mut(&mut self) -> PortalsMut<'_> {
+        PortalsMut::new(&mut self.portals)
+    }
+}
+
+impl Shape for DenseMap {
+    fn box_shape_clone(&self) -> Box<dyn Shape> {
+        Box::new((*self).clone())
+    }
+}
+
+impl SubMapCollection for DenseMap {
+    fn add_sub_map(&mut self, local_position: Position, target: MapId) {
+        let old_size = *self.size();
+        // As in a `SparseMap`, sub-map positions are stored offset by this map's position.
+        let stored_position = local_position + *self.position();
+        let mut target_area = *MAPS.read()[target].read().area();
+        let area = self.area_mut();
+        *target_area.position_mut() = *target_area.position() + stored_position;
+        let right_pin = area.right();
+        let bottom_pin = area.bottom();
+        *area.position_mut().x_mut() = area.position().x().min(target_area.position().x());
+        *area.position_mut().y_mut() = area.position().y().min(target_area.position().y());
+        area.right_set(area.right().max(target_area.right()).max(right_pin));
+        area.bottom_set(area.bottom().max(target_area.bottom()).max(bottom_pin));
+
+        // The area's size may have changed; keep the backing `Vec` in step with it.
+        let new_size = *self.size();
+        *self.size_mut() = old_size;
+        self.resize(new_size);
+
+        self.sub_maps.push(SubMap::new(stored_position, target))
+    }
+
+    fn get_sub_map_at(&self, index: usize) -> Option<&SubMap> {
+        self.sub_maps.get(index)
+    }
+
+    fn get_sub_map_at_mut(&mut self, index: usize) -> Option<&mut SubMap> {
+        self.sub_maps.get_mut(index)
+    }
+
+    fn sub_map_count(&self) -> usize {
+        self.sub_maps.len()
+    }
+
+    fn sub_maps(&self) -> SubMaps<'_> {
+        SubMaps::new(&self.sub_maps)
+    }
+
+    fn sub_maps_mut(&mut self) -> SubMapsMut<'_> {
+        SubMapsMut::new(&mut self.sub_maps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SparseMap;
+
+    fn count_floor_tiles(map: &dyn Map) -> usize {
+        let mut count = 0;
+        for y in 0..map.height() as i32 {
+            for x in 0..map.width() as i32 {
+                if map.tile_type_at_local(Position::new(x, y)) == Some(TileType::Floor) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn negative_positions_are_not_written_through() {
+        let sub_map = SparseMap::new_filled(Size::new(3, 3), TileType::Floor);
+        let mut dense_map = DenseMap::default();
+        dense_map.add_sub_map(Position::zero(), sub_map);
+        *dense_map.position_mut() = Position::new(1, 1);
+        assert!(dense_map.tile_type_at_local(Position::new(-1, -1)) == Some(TileType::Floor));
+
+        assert!(dense_map
+            .tile_type_at_local_set(Position::new(-1, -1), TileType::Wall)
+            .is_none());
+        let maps = MAPS.read();
+        assert!(maps[sub_map].read().tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+        drop(maps);
+
+        dense_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+        let maps = MAPS.read();
+        assert!(
+            maps[sub_map].read().tile_type_at_local(Position::new(1, 1)) == Some(TileType::Wall)
+        );
+    }
+
+    #[test]
+    fn full_map_iteration_matches_sparse_map() {
+        const SIDE: i32 = 256;
+
+        let mut dense_map = DenseMap::default();
+        let mut sparse_map = SparseMap::default();
+        for y in 0..SIDE {
+            for x in 0..SIDE {
+                dense_map.tile_type_at_local_set(Position::new(x, y), TileType::Floor);
+                sparse_map.tile_type_at_local_set(Position::new(x, y), TileType::Floor);
+            }
+        }
+        assert!(*dense_map.size() == *sparse_map.size());
+
+        let dense_count = count_floor_tiles(&dense_map);
+        let sparse_count = count_floor_tiles(&sparse_map);
+        assert!(
+            dense_count == (SIDE * SIDE) as usize,
+            "DenseMap has {} of {} floor tiles",
+            dense_count,
+            SIDE * SIDE
+        );
+        assert!(dense_count == sparse_count);
+    }
+}
@@ -4,6 +4,12 @@
 //! Defines various map-related enums, structs, and types (traits are contained in `dungen_minion_rooms_abstract`) for the `dungen_minion` crate.
 //!
 //! As the purpose of this crate is to provide concrete data types for `dungen_minion` and `dungen_minion`'s other dependent crates to consume, only implementation-specific details are defined here, while their general usages are usually defined in `dungen_minion_rooms_abstract`, and specific other details in `dungen_minion_geometry`.
+//!
+//! # Locking
+//!
+//! Every map is registered in `MAPS` behind its own lock, and refers to its sub-maps and `Portal` targets by `MapId`. A method or function which reads another map, whether a sub-map, a `Portal` target, or a map passed to it, takes a read lock on that map while reading it; as such, none may be called while holding a write lock on a map it reads. Setting a tile through `tile_type_at_local_set` writes it through to the sub-maps which cover it, and so also takes a write lock on each of them. Registering a new map takes a write lock on `MAPS` itself, so a method or function which registers maps, such as `SparseMap::new` or `import_json`, must not be called while holding any lock on `MAPS`.
+//!
+//! Methods and functions which lock maps in other ways, such as by rewriting whole sub-maps, say so.
 
 // External includes.
 pub use dungen_minion_rooms_abstract::*;
@@ -11,9 +17,74 @@ pub use dungen_minion_rooms_abstract::*;
 // Standard includes.
 
 // Internal includes.
+mod corridor_kind;
+mod dense_map;
+mod line_of_sight;
+mod map_batch;
+mod map_builder;
+mod map_diff;
+mod map_error;
+mod map_graph;
+#[cfg(feature = "json")]
+mod map_json;
+mod map_overlap;
+mod map_registry;
+mod map_snapshot;
+mod map_tree;
+mod neighbors;
+mod pathfinding;
+mod portal_kind;
+mod portal_lookup;
+mod rle_error;
+mod rotation;
+#[cfg(feature = "serde")]
+mod serde_defs;
 mod sparse_map;
+mod sub_map_resolution;
+mod tile_data;
+mod tile_source;
+mod to_ascii;
+mod unregistered_map;
+mod xorshift;
 
-pub use sparse_map::SparseMap;
+pub use corridor_kind::CorridorKind;
+pub use dense_map::DenseMap;
+pub use line_of_sight::LineOfSight;
+pub use map_batch::generate_maps;
+pub use map_builder::MapBuilder;
+pub use map_diff::diff_maps;
+pub use map_error::MapError;
+use map_graph::sub_maps_reach;
+pub use map_graph::{
+    all_portals, link_maps, reachable_maps, tile_type_at_world, total_floor_area, try_add_sub_map,
+};
+#[cfg(feature = "json")]
+pub use map_json::{export_json, import_json, ImportError};
+pub use map_overlap::{maps_overlap, overlap_area};
+pub use map_registry::{
+    is_map_registered, registered_map_count, registered_map_ids, try_get_map, unregister_map,
+    MapHandle,
+};
+pub use map_snapshot::MapSnapshot;
+use map_tree::MapTree;
+pub use neighbors::Neighbors;
+pub use pathfinding::Pathfinding;
+pub use portal_kind::PortalKind;
+pub use portal_lookup::PortalLookup;
+pub use rle_error::RleError;
+use rotation::{rotate_sub_maps, rotation_adjustment};
+#[cfg(feature = "serde")]
+pub use serde_defs::{
+    AreaDef, CardinalDirectionDef, PortalDef, PositionDef, SizeDef, SubMapDef, TileTypeDef,
+};
+use sparse_map::{sparse_map_copy, tile_type_index, void_visible_cmp};
+pub use sparse_map::{SparseMap, TileObserver, TilePriority};
+use sub_map_resolution::{for_each_resolved_sub_map, with_depth_limit};
+pub use tile_data::TileData;
+pub use tile_source::TileSource;
+pub use to_ascii::ToAscii;
+use unregistered_map::UnregisteredMap;
+use xorshift::XorShift;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,92 @@
+// External includes.
+use super::{Map, TileType};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Checks for unobstructed straight lines between local `Position`s of a [`Map`](trait.Map.html).
+///
+/// `LineOfSight` is implemented for every `Map`, including the `Box<dyn Map>`s stored in `MAPS`.
+pub trait LineOfSight: Map {
+    /// Returns whether the straight line between `from` and `to` is unobstructed; that is, whether no tile on the line, other than `from` and `to` themselves, is one for which `blocks` returns `true`.
+    ///
+    /// The line is traced with Bresenham's algorithm, always from the endpoint with the lower `y`, and then the lower `x`, so the result does not depend upon the order of the arguments. Tiles are resolved through `tile_type_at_local`; positions with no tile never block.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(6, 6)), TileType::Floor);
+    /// let blocks = |tile_type| tile_type == TileType::Wall;
+    ///
+    /// assert!(sparse_map.line_of_sight(Position::new(0, 0), Position::new(5, 3), &blocks));
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(3, 2), TileType::Wall);
+    /// assert!(!sparse_map.line_of_sight(Position::new(0, 0), Position::new(5, 3), &blocks));
+    /// assert!(!sparse_map.line_of_sight(Position::new(5, 3), Position::new(0, 0), &blocks));
+    ///
+    /// // The endpoints themselves never block.
+    /// assert!(sparse_map.line_of_sight(Position::new(3, 2), Position::new(3, 5), &blocks));
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 3), TileType::Wall);
+    /// for (from, to) in [
+    ///     (Position::new(0, 5), Position::new(5, 0)),
+    ///     (Position::new(0, 5), Position::new(4, 0)),
+    ///     (Position::new(1, 5), Position::new(5, 2)),
+    /// ] {
+    ///     assert!(
+    ///         sparse_map.line_of_sight(from, to, &blocks)
+    ///             == sparse_map.line_of_sight(to, from, &blocks)
+    ///     );
+    /// }
+    /// assert!(!sparse_map.line_of_sight(Position::new(0, 5), Position::new(5, 0), &blocks));
+    /// assert!(sparse_map.line_of_sight(Position::new(2, 3), Position::new(2, 3), &blocks));
+    /// ```
+    fn line_of_sight(
+        &self,
+        from: Position,
+        to: Position,
+        blocks: &dyn Fn(TileType) -> bool,
+    ) -> bool {
+        let (start, end) = if (from.y(), from.x()) <= (to.y(), to.x()) {
+            (from, to)
+        } else {
+            (to, from)
+        };
+
+        if start == end {
+            return true;
+        }
+
+        let delta_x = (end.x() - start.x()).abs();
+        let delta_y = -(end.y() - start.y()).abs();
+        let step_x = if start.x() < end.x() { 1 } else { -1 };
+        let step_y = if start.y() < end.y() { 1 } else { -1 };
+        let mut error = delta_x + delta_y;
+        let mut x = start.x();
+        let mut y = start.y();
+        loop {
+            let doubled_error = 2 * error;
+            if doubled_error >= delta_y {
+                error += delta_y;
+                x += step_x;
+            }
+            if doubled_error <= delta_x {
+                error += delta_x;
+                y += step_y;
+            }
+
+            let position = Position::new(x, y);
+            if position == end {
+                return true;
+            }
+            if self.tile_type_at_local(position).is_some_and(blocks) {
+                return false;
+            }
+        }
+    }
+}
+
+impl<TMap> LineOfSight for TMap where TMap: Map + ?Sized {}
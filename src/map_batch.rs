@@ -0,0 +1,68 @@
+// External includes.
+use super::MapId;
+
+// Standard includes.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Internal includes.
+
+/// Calls `f` with every index from `0` to `count`, across a scoped pool of threads, and returns the `MapId` which `f` returns for each, in order of index.
+///
+/// `f` is expected to build and register a map, and to use the index, rather than any shared state, to vary it, so that the results are reproducible regardless of how the indices are shared between threads. Threads take the next unclaimed index as they finish each map, up to one thread per available core, and all have finished when this returns. `get_new_map_id` and `register_map` take their own locks, so `f` may call them freely; as such, this must not be called while holding a lock on `MAPS`. If `f` panics, the panic is propagated once every thread has finished.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// # use std::collections::HashSet;
+/// let map_ids = generate_maps(100, |index| {
+///     SparseMap::new_room(Size::new(3 + (index % 5) as u32, 3))
+/// });
+/// assert!(map_ids.len() == 100);
+/// assert!(map_ids.iter().collect::<HashSet<_>>().len() == 100);
+///
+/// let maps = MAPS.read();
+/// for (index, map_id) in map_ids.iter().enumerate() {
+///     let map = maps[*map_id].read();
+///     assert!(map.map_id() == *map_id);
+///     assert!(map.width() == 3 + (index % 5) as u32);
+/// }
+/// ```
+pub fn generate_maps<F>(count: usize, f: F) -> Vec<MapId>
+where
+    F: Fn(usize) -> MapId + Sync,
+{
+    let thread_count = std::thread::available_parallelism()
+        .map(|thread_count| thread_count.get())
+        .unwrap_or(1)
+        .min(count);
+    let next_index = AtomicUsize::new(0);
+
+    let mut output = vec![0; count];
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut generated = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            return generated;
+                        }
+                        generated.push((index, f(index)));
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let generated = handle
+                .join()
+                .unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+            for (index, map_id) in generated {
+                output[index] = map_id;
+            }
+        }
+    });
+
+    output
+}
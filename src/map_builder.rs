@@ -0,0 +1,147 @@
+// External includes.
+use super::{register_map, MapId, PortalCollection, SubMapCollection, TileType};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+use super::SparseMap;
+
+/// A step recorded by a `MapBuilder`, to be applied on `build`.
+#[derive(Clone, Debug)]
+enum BuildStep {
+    Fill(TileType),
+    Border(TileType),
+    Portal(Position, CardinalDirection, Position, MapId),
+    SubMap(Position, MapId),
+}
+
+/// Builds a [`SparseMap`](struct.SparseMap.html) from a chain of steps, which are recorded, and only applied, in order, to a fresh `SparseMap` on [`build`](#method.build).
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let target = SparseMap::new();
+/// let sub_map = SparseMap::new_filled(Size::new(1, 1), TileType::Portal);
+/// let map_id = MapBuilder::new()
+///     .size(Size::new(5, 4))
+///     .fill(TileType::Floor)
+///     .border(TileType::Wall)
+///     .portal(Position::new(4, 1), CardinalDirection::East, Position::zero(), target)
+///     .sub_map(Position::new(2, 2), sub_map)
+///     .build();
+///
+/// let maps = MAPS.read();
+/// let map = maps[map_id].read();
+/// assert!(*map.size() == Size::new(5, 4));
+/// assert!(map.to_ascii() == "#####\n#...+\n#.+.#\n#####");
+/// assert!(map.portal_count() == 1);
+/// assert!(map.get_portal_at(0).unwrap().target() == target);
+/// assert!(map.sub_map_count() == 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapBuilder {
+    size: Size,
+    steps: Vec<BuildStep>,
+}
+
+impl MapBuilder {
+    /// Creates a `MapBuilder` with no steps, for a `SparseMap` of `Size::zero()`.
+    pub fn new() -> Self {
+        Self {
+            size: Size::zero(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Sets the border of the `SparseMap`'s area to `tile_type`, as per `SparseMap::fill_border`, using the size given by [`size`](#method.size).
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn border(mut self, tile_type: TileType) -> Self {
+        self.steps.push(BuildStep::Border(tile_type));
+        self
+    }
+
+    /// Creates and registers the `SparseMap`, with a fresh `MapId`, applying every step in the order given, and returns its `MapId`.
+    ///
+    /// The `SparseMap` is given the size set by [`size`](#method.size) before any step is applied, and may grow to fit the tiles, `Portal`s, and sub-maps added by them.
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn build(self) -> MapId {
+        let area = Area::new(Position::zero(), self.size);
+        let mut sparse_map = SparseMap::default();
+        *sparse_map.size_mut() = self.size;
+        for step in self.steps {
+            match step {
+                BuildStep::Fill(tile_type) => sparse_map.fill_area(area, tile_type),
+                BuildStep::Border(tile_type) => sparse_map.fill_border(area, tile_type),
+                BuildStep::Portal(
+                    local_position,
+                    portal_to_map_facing,
+                    portal_to_map_position,
+                    target,
+                ) => sparse_map.add_portal(
+                    local_position,
+                    portal_to_map_facing,
+                    portal_to_map_position,
+                    target,
+                ),
+                BuildStep::SubMap(local_position, target) => {
+                    sparse_map.add_sub_map(local_position, target)
+                }
+            }
+        }
+
+        register_map(sparse_map)
+    }
+
+    /// Sets every tile of the `SparseMap`'s area to `tile_type`, as per `SparseMap::fill_area`, using the size given by [`size`](#method.size).
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn fill(mut self, tile_type: TileType) -> Self {
+        self.steps.push(BuildStep::Fill(tile_type));
+        self
+    }
+
+    /// Adds a `Portal`, as per `add_portal`.
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn portal(
+        mut self,
+        local_position: Position,
+        portal_to_map_facing: CardinalDirection,
+        portal_to_map_position: Position,
+        target: MapId,
+    ) -> Self {
+        self.steps.push(BuildStep::Portal(
+            local_position,
+            portal_to_map_facing,
+            portal_to_map_position,
+            target,
+        ));
+        self
+    }
+
+    /// Sets the size of the `SparseMap`, which is used by every [`fill`](#method.fill) and [`border`](#method.border) step, wherever it appears in the chain; if it is set more than once, the last size is used.
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Adds the map `target` as a sub-map, at `local_position`, as per `add_sub_map`.
+    ///
+    /// See [`MapBuilder`](struct.MapBuilder.html) for an example.
+    pub fn sub_map(mut self, local_position: Position, target: MapId) -> Self {
+        self.steps.push(BuildStep::SubMap(local_position, target));
+        self
+    }
+}
+
+impl Default for MapBuilder {
+    /// Creates a `MapBuilder` with no steps, as per [`new`](#method.new).
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,59 @@
+// External includes.
+use super::{Map, MapId, TileType, MAPS};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Returns every local `Position` at which the registered maps `a` and `b` resolve to different tiles, along with the `TileType` from `a`, and then from `b`, in order of row, and then column.
+///
+/// The union of both maps' local areas is compared, from `Position::zero()` to the furthest right and bottom of either; positions outside of one map's area read as `None` for that map. Tiles are resolved through `tile_type_at_local`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let area = Area::new(Position::zero(), Size::new(4, 3));
+/// let mut sparse_map = SparseMap::default();
+/// sparse_map.fill_area(area, TileType::Floor);
+/// sparse_map.fill_border(area, TileType::Wall);
+/// let a = sparse_map.extract_area(area);
+/// assert!(diff_maps(a, a).is_empty());
+///
+/// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Wall);
+/// let b = register_map(sparse_map.clone());
+/// assert!(diff_maps(a, b) == vec![(Position::new(2, 1), Some(TileType::Floor), Some(TileType::Wall))]);
+/// assert!(diff_maps(b, a) == vec![(Position::new(2, 1), Some(TileType::Wall), Some(TileType::Floor))]);
+///
+/// sparse_map.tile_type_at_local_set(Position::new(5, 0), TileType::Floor);
+/// let c = sparse_map.extract_area(Area::new(Position::zero(), *sparse_map.size()));
+/// assert!(diff_maps(b, c) == vec![(Position::new(5, 0), None, Some(TileType::Floor))]);
+/// ```
+pub fn diff_maps(a: MapId, b: MapId) -> Vec<(Position, Option<TileType>, Option<TileType>)> {
+    let maps = MAPS.read();
+    let map_a = maps[a].read();
+    let map_b = maps[b].read();
+    let width = map_a.width().max(map_b.width()) as i32;
+    let height = map_a.height().max(map_b.height()) as i32;
+    let tile_type_at = |map: &dyn Map, position: Position| {
+        if map.size().intersects_local_position(position) {
+            map.tile_type_at_local(position)
+        } else {
+            None
+        }
+    };
+
+    let mut output = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let position = Position::new(x, y);
+            let tile_type_a = tile_type_at(&**map_a, position);
+            let tile_type_b = tile_type_at(&**map_b, position);
+            if tile_type_a != tile_type_b {
+                output.push((position, tile_type_a, tile_type_b));
+            }
+        }
+    }
+
+    output
+}
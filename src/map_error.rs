@@ -0,0 +1,38 @@
+// External includes.
+use super::MapId;
+use crate::geometry::*;
+
+// Standard includes.
+use std::fmt;
+
+// Internal includes.
+
+/// The reasons the `try_` operations on maps, such as [`SparseMap::try_tile_type_at_local_set`](struct.SparseMap.html#method.try_tile_type_at_local_set), can fail; the lenient operations they mirror ignore, or work around, the same input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MapError {
+    /// The local `Position` is outside of the map's area, or, when setting a tile, to the left of, or above, the area's origin, where the map cannot expand; holds the `Position`.
+    OutOfBounds(Position),
+    /// The `MapId` is not registered in `MAPS`, such as the target of a `Portal` or `SubMap` which was removed with [`unregister_map`](fn.unregister_map.html); holds the `MapId`.
+    DanglingTarget(MapId),
+    /// Adding the `SubMap` would create a cycle of sub-maps, as its target already contains the map; holds the target's `MapId`.
+    Cycle(MapId),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::OutOfBounds(position) => write!(
+                f,
+                "local position ({}, {}) is out of bounds",
+                position.x(),
+                position.y()
+            ),
+            MapError::DanglingTarget(map_id) => write!(f, "map {} is not registered", map_id),
+            MapError::Cycle(map_id) => {
+                write!(f, "adding map {} as a sub-map would create a cycle", map_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
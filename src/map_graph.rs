@@ -0,0 +1,340 @@
+// External includes.
+use super::{MapId, TileType, MAPS};
+use crate::geometry::*;
+
+// Standard includes.
+use std::collections::HashSet;
+
+// Internal includes.
+use super::is_map_registered;
+use super::with_depth_limit;
+use super::MapError;
+
+/// Returns every `Portal` of `root`, and of every registered map reachable from it by following `Portal` targets, as per [`reachable_maps`](fn.reachable_maps.html), as the `MapId` of the map which owns it, its local `Position`, the facing it leads to, and its target.
+///
+/// Each map is visited once, so cycles of `Portal`s are safe. Maps are listed in order of `MapId`, and each map's `Portal`s in the order they were added. `Portal`s which target maps which are not registered are still included, although those maps are not followed.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new_room(Size::new(4, 3));
+/// let b = SparseMap::new_room(Size::new(5, 4));
+/// let c = SparseMap::new_room(Size::new(5, 5));
+/// link_maps(a, Position::new(3, 1), b, Position::new(0, 1), CardinalDirection::East);
+/// link_maps(b, Position::new(4, 1), c, Position::new(0, 1), CardinalDirection::East);
+/// link_maps(c, Position::new(2, 0), a, Position::new(1, 2), CardinalDirection::North);
+///
+/// let portals = all_portals(a);
+/// assert!(portals.len() == 6);
+/// assert!(all_portals(c) == portals);
+/// assert!(portals.contains(&(a, Position::new(3, 1), CardinalDirection::East, b)));
+/// assert!(portals.contains(&(a, Position::new(1, 2), CardinalDirection::South, c)));
+/// assert!(portals.contains(&(b, Position::new(0, 1), CardinalDirection::West, a)));
+/// assert!(portals.contains(&(b, Position::new(4, 1), CardinalDirection::East, c)));
+/// assert!(portals.contains(&(c, Position::new(0, 1), CardinalDirection::West, b)));
+/// assert!(portals.contains(&(c, Position::new(2, 0), CardinalDirection::North, a)));
+///
+/// let d = SparseMap::new();
+/// assert!(all_portals(d).is_empty());
+/// ```
+pub fn all_portals(root: MapId) -> Vec<(MapId, Position, CardinalDirection, MapId)> {
+    let mut map_ids: Vec<MapId> = reachable_maps(root, false).into_iter().collect();
+    map_ids.sort_unstable();
+
+    let mut output = Vec::new();
+    for map_id in map_ids {
+        let maps = MAPS.read();
+        let map = maps[map_id].read();
+        output.extend(map.portals().into_iter().map(|portal| {
+            (
+                map_id,
+                *portal.local_position(),
+                *portal.portal_to_map_facing(),
+                portal.target(),
+            )
+        }));
+    }
+
+    output
+}
+
+/// Links two registered maps with a pair of `Portal`s: one from `a` to `b` at `a_pos`, facing `a_facing`, and a reciprocal one from `b` to `a` at `b_pos`, facing the opposite direction.
+///
+/// Each `Portal`'s end-point is the other's local `Position`. `a` and `b` may be the same map. Only one map is locked at a time, so this must not be called while holding a lock on either map; a `Portal` added to a map which is not registered is discarded, as per [`unregister_map`](fn.unregister_map.html).
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new();
+/// let b = SparseMap::new();
+/// link_maps(a, Position::new(3, 1), b, Position::new(0, 2), CardinalDirection::East);
+///
+/// let maps = MAPS.read();
+/// let map_a = maps[a].read();
+/// let map_b = maps[b].read();
+/// assert!(map_a.portal_count() == 1 && map_b.portal_count() == 1);
+///
+/// let a_to_b = map_a.get_portal_at(0).unwrap();
+/// assert!(a_to_b.target() == b);
+/// assert!(*a_to_b.local_position() == Position::new(3, 1));
+/// assert!(*a_to_b.portal_to_map_position() == Position::new(0, 2));
+/// assert!(*a_to_b.portal_to_map_facing() == CardinalDirection::East);
+///
+/// let b_to_a = map_b.get_portal_at(0).unwrap();
+/// assert!(b_to_a.target() == a);
+/// assert!(*b_to_a.local_position() == Position::new(0, 2));
+/// assert!(*b_to_a.portal_to_map_position() == Position::new(3, 1));
+/// assert!(*b_to_a.portal_to_map_facing() == CardinalDirection::West);
+/// # drop(map_a);
+/// # drop(map_b);
+/// # drop(maps);
+///
+/// link_maps(a, Position::new(0, 0), a, Position::new(2, 2), CardinalDirection::North);
+/// assert!(MAPS.read()[a].read().portal_count() == 3);
+/// ```
+pub fn link_maps(
+    a: MapId,
+    a_pos: Position,
+    b: MapId,
+    b_pos: Position,
+    a_facing: CardinalDirection,
+) {
+    let maps = MAPS.read();
+    // The write locks are taken one after the other, so that linking a map to itself does not deadlock.
+    if let Some(map) = maps.get(a) {
+        map.write().add_portal(a_pos, a_facing, b_pos, b);
+    }
+    if let Some(map) = maps.get(b) {
+        map.write().add_portal(b_pos, -a_facing, a_pos, a);
+    }
+}
+
+/// Returns the `MapId` of every registered map reachable from `start` by following `Portal` targets, including `start` itself; if `include_sub_maps` is `true`, `SubMap` targets are followed as well.
+///
+/// Each map is visited at most once, so cycles of `Portal`s are safe. Targets which are not registered, such as those removed with [`unregister_map`](fn.unregister_map.html), are not included, nor followed.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new();
+/// let b = SparseMap::new();
+/// let c = SparseMap::new();
+/// let d = SparseMap::new();
+/// let e = SparseMap::new();
+/// {
+///     let maps = MAPS.read();
+///     let link = |from: MapId, to: MapId| {
+///         maps[from]
+///             .write()
+///             .add_portal(Position::zero(), CardinalDirection::East, Position::zero(), to);
+///     };
+///     link(a, b);
+///     link(b, c);
+///     link(c, a);
+///     maps[c].write().add_sub_map(Position::zero(), e);
+/// }
+///
+/// let reachable = reachable_maps(a, false);
+/// assert!(reachable.len() == 3);
+/// assert!(reachable.contains(&a) && reachable.contains(&b) && reachable.contains(&c));
+/// assert!(!reachable.contains(&d));
+/// assert!(!reachable.contains(&e));
+///
+/// let reachable = reachable_maps(a, true);
+/// assert!(reachable.len() == 4);
+/// assert!(reachable.contains(&e));
+/// ```
+pub fn reachable_maps(start: MapId, include_sub_maps: bool) -> HashSet<MapId> {
+    let mut output = HashSet::new();
+    let mut stack = vec![start];
+    while let Some(map_id) = stack.pop() {
+        if output.contains(&map_id) {
+            continue;
+        }
+
+        let maps = MAPS.read();
+        let map = match maps.get(map_id) {
+            Some(map) => map.read(),
+            None => continue,
+        };
+        if map.map_id() != map_id {
+            continue;
+        }
+
+        output.insert(map_id);
+        stack.extend(map.portals().into_iter().map(|portal| portal.target()));
+        if include_sub_maps {
+            stack.extend(map.sub_maps().into_iter().map(|sub_map| sub_map.value()));
+        }
+    }
+
+    output
+}
+
+/// Returns whether `to` is `from`, or is reachable from `from` by following only `SubMap` targets. Targets which are not registered are not followed.
+pub(crate) fn sub_maps_reach(from: MapId, to: MapId) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(map_id) = stack.pop() {
+        if map_id == to {
+            return true;
+        }
+        if !visited.insert(map_id) {
+            continue;
+        }
+
+        let maps = MAPS.read();
+        let map = match maps.get(map_id) {
+            Some(map) => map.read(),
+            None => continue,
+        };
+        if map.map_id() != map_id {
+            continue;
+        }
+
+        stack.extend(map.sub_maps().into_iter().map(|sub_map| sub_map.value()));
+    }
+
+    false
+}
+
+/// Returns the tile which the registered map `root` resolves at the world `Position` `world`, or `None` if `root` is not registered.
+///
+/// A map's local `Position`s start from its own `position()`, so `world` is converted to `root`'s local `Position` by subtracting `root`'s `position()`, and resolved through `tile_type_at_local`, which already consults sub-maps, to any depth; a sub-map placed at a local `Position` of `root` covers the world `Position`s from `root`'s `position()` plus that local `Position`. The `position()`s of the sub-maps themselves are already taken into account by `tile_type_at_local`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let sub_map = SparseMap::new();
+/// MAPS.read()[sub_map]
+///     .write()
+///     .tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+///
+/// let mut sparse_map = SparseMap::default();
+/// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+/// sparse_map.add_sub_map(Position::new(2, 2), sub_map);
+/// sparse_map.translate(Position::new(10, 10));
+/// let root = register_map(sparse_map);
+///
+/// assert!(tile_type_at_world(root, Position::new(10, 10)) == Some(TileType::Wall));
+/// assert!(tile_type_at_world(root, Position::new(13, 13)) == Some(TileType::Floor));
+/// assert!(tile_type_at_world(root, Position::new(3, 3)) == None);
+/// assert!(tile_type_at_world(root, Position::new(0, 0)) == None);
+///
+/// unregister_map(root);
+/// assert!(tile_type_at_world(root, Position::new(10, 10)) == None);
+/// ```
+pub fn tile_type_at_world(root: MapId, world: Position) -> Option<TileType> {
+    let maps = MAPS.read();
+    let map = maps.get(root)?.read();
+    if map.map_id() != root {
+        return None;
+    }
+
+    map.tile_type_at_local(world - *map.position())
+}
+
+/// Returns the number of tiles for which `is_floor` returns `true` across `root` and every registered map reachable from it, as per [`reachable_maps`](fn.reachable_maps.html), following both `Portal` and `SubMap` targets.
+///
+/// Each map is visited once, so cycles are safe, and each map counts only its own tiles within its area, as per `tile_type_at_local` with sub-maps left unresolved; the tiles of a sub-map are counted once, for the sub-map itself, rather than again for every map which contains it. This is honoured by the maps provided by this crate, such as `SparseMap` and `DenseMap`; other `Map` implementations may count their sub-maps' tiles as their own.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new_room(Size::new(4, 3));
+/// let b = SparseMap::new_room(Size::new(5, 4));
+/// let c = SparseMap::new_room(Size::new(5, 5));
+/// link_maps(a, Position::new(3, 1), b, Position::new(0, 1), CardinalDirection::East);
+/// link_maps(b, Position::new(4, 1), c, Position::new(0, 1), CardinalDirection::East);
+/// link_maps(c, Position::new(2, 0), a, Position::new(1, 2), CardinalDirection::North);
+///
+/// let is_floor = |tile_type| tile_type == TileType::Floor;
+/// assert!(total_floor_area(a, &is_floor) == 2 + 6 + 9);
+/// assert!(total_floor_area(c, &is_floor) == 2 + 6 + 9);
+///
+/// // The sub-map's floor is counted once, although it is also read through `a`.
+/// let d = SparseMap::new_filled(Size::new(1, 1), TileType::Floor);
+/// try_add_sub_map(a, Position::zero(), d).unwrap();
+/// assert!(MAPS.read()[a].read().tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+/// assert!(total_floor_area(a, &is_floor) == 2 + 6 + 9 + 1);
+///
+/// let is_walkable = |tile_type| tile_type == TileType::Floor || tile_type == TileType::Portal;
+/// assert!(total_floor_area(a, &is_walkable) == 18 + 6);
+/// ```
+pub fn total_floor_area(root: MapId, is_floor: &dyn Fn(TileType) -> bool) -> usize {
+    let mut output = 0;
+    for map_id in reachable_maps(root, true) {
+        let maps = MAPS.read();
+        let map = maps[map_id].read();
+        with_depth_limit(0, || {
+            for y in 0..map.height() as i32 {
+                for x in 0..map.width() as i32 {
+                    if map
+                        .tile_type_at_local(Position::new(x, y))
+                        .is_some_and(is_floor)
+                    {
+                        output += 1;
+                    }
+                }
+            }
+        });
+    }
+
+    output
+}
+
+/// Adds `target` as a sub-map of the registered map `map_id`, at `local_position`, unless doing so would create a cycle of sub-maps.
+///
+/// Returns `MapError::DanglingTarget` if either `map_id` or `target` is not registered, and `MapError::Cycle` if a cycle would be created; the sub-map is only added if `Ok` is returned. A cycle would be created if `target` is `map_id`, or if `target` already contains `map_id`, directly or transitively, as a sub-map; this is found by walking the sub-maps of `target` through `MAPS`. Such cycles are otherwise broken when resolving tiles, as per `SparseMap`'s `tile_type_at_local`, but are best avoided. Use [`SparseMap::try_add_sub_map`](struct.SparseMap.html#method.try_add_sub_map) for a `SparseMap` which is not registered.
+///
+/// This takes read locks on `target` and its sub-maps, and then a write lock on `map_id`, and so must not be called while holding a lock on any of them.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new();
+/// let b = SparseMap::new();
+/// let c = SparseMap::new();
+/// assert!(try_add_sub_map(a, Position::zero(), b) == Ok(()));
+/// assert!(try_add_sub_map(b, Position::zero(), c) == Ok(()));
+///
+/// assert!(try_add_sub_map(b, Position::zero(), a) == Err(MapError::Cycle(a)));
+/// assert!(try_add_sub_map(c, Position::zero(), a) == Err(MapError::Cycle(a)));
+/// assert!(try_add_sub_map(a, Position::zero(), a) == Err(MapError::Cycle(a)));
+/// assert!(MAPS.read()[b].read().sub_map_count() == 1);
+///
+/// // Sharing a sub-map is not a cycle.
+/// assert!(try_add_sub_map(a, Position::new(1, 1), c) == Ok(()));
+/// assert!(MAPS.read()[a].read().sub_map_count() == 2);
+///
+/// let removed = SparseMap::new();
+/// unregister_map(removed);
+/// assert!(try_add_sub_map(a, Position::zero(), removed) == Err(MapError::DanglingTarget(removed)));
+/// assert!(try_add_sub_map(removed, Position::zero(), c) == Err(MapError::DanglingTarget(removed)));
+/// ```
+pub fn try_add_sub_map(
+    map_id: MapId,
+    local_position: Position,
+    target: MapId,
+) -> Result<(), MapError> {
+    if !is_map_registered(target) {
+        return Err(MapError::DanglingTarget(target));
+    }
+    if sub_maps_reach(target, map_id) {
+        return Err(MapError::Cycle(target));
+    }
+
+    let maps = MAPS.read();
+    match maps.get(map_id) {
+        Some(map) => {
+            let mut map = map.write();
+            if map.map_id() != map_id {
+                return Err(MapError::DanglingTarget(map_id));
+            }
+
+            map.add_sub_map(local_position, target);
+            Ok(())
+        }
+        None => Err(MapError::DanglingTarget(map_id)),
+    }
+}
@@ -0,0 +1,151 @@
+// External includes.
+use super::{register_map, MapId, MAPS};
+
+// Standard includes.
+use std::fmt;
+
+// Internal includes.
+use super::MapError;
+use super::MapTree;
+
+/// The reasons [`import_json`](fn.import_json.html) can fail.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ImportError {
+    /// The input is not valid JSON; holds a description of where, and why.
+    Syntax(String),
+    /// The input is valid JSON, but does not match the format produced by [`export_json`](fn.export_json.html), such as when a field is missing, or a `TileType` is unknown; holds a description of the mismatch.
+    Format(String),
+    /// A `Portal` or `SubMap` target is not an index into `"maps"`; holds the index. As the root is always index `0`, this is also reported as `DanglingIndex(0)` if `"maps"` is empty.
+    DanglingIndex(usize),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Syntax(message) => write!(f, "invalid JSON: {}", message),
+            ImportError::Format(message) => write!(f, "invalid map tree: {}", message),
+            ImportError::DanglingIndex(index) => {
+                write!(f, "map index {} is not in the map tree", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Data => ImportError::Format(error.to_string()),
+            _ => ImportError::Syntax(error.to_string()),
+        }
+    }
+}
+
+/// Exports the registered map `root`, and every map reachable from it through its sub-maps and `Portal`s, as a JSON `String`.
+///
+/// Each map is assigned an index into a `"maps"` array, with `root` at index `0`. Each map is an object holding its `"area"`, the `"tiles"` stored directly in it, including `TileType::Void` tiles, as `[x, y, tile_type]` triples in local coordinates, its `"portals"`, each with the `"kind"` of `SparseMap` portals, its `"sub_maps"`, and, for a `SparseMap`, the `"layers"` above layer `0`, the `"doors"`, as `[x, y, open]` triples, and the `"tile_data"`, as `[x, y, tile_data]` triples; `Portal` and `SubMap` targets are stored as indices into `"maps"`, so cycles are preserved. This is the same format in which a `SparseMap` is serialized.
+///
+/// Returns `MapError::DanglingTarget` if `root`, or any map reachable from it, is not registered.
+///
+/// The root is copied out of `MAPS`, and each lock is released before the next map is read, so no lock is held while resolving tiles.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new_room(Size::new(4, 3));
+/// let b = SparseMap::new_room(Size::new(3, 3));
+/// link_maps(a, Position::new(3, 1), b, Position::new(0, 1), CardinalDirection::East);
+///
+/// let json = export_json(a).unwrap();
+/// let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+/// let maps = value["maps"].as_array().unwrap();
+/// assert!(maps.len() == 2);
+/// assert!(maps[0]["area"]["size"] == serde_json::json!({ "width": 4, "height": 3 }));
+/// assert!(maps[0]["tiles"][0] == serde_json::json!([0, 0, "Wall"]));
+/// assert!(maps[0]["portals"][0]["target"] == 1);
+/// assert!(maps[1]["portals"][0]["target"] == 0);
+/// assert!(maps[0]["portals"][0]["kind"] == "Open");
+///
+/// let imported: SparseMap = serde_json::from_str(&json).unwrap();
+/// let maps = MAPS.read();
+/// assert!(imported.to_ascii() == maps[a].read().to_ascii());
+/// let portal = imported.get_portal_at(0).unwrap();
+/// assert!(*portal.portal_to_map_position() == Position::new(0, 1));
+/// assert!(maps[portal.target()].read().get_portal_at(0).unwrap().target() == imported.map_id());
+/// drop(maps);
+///
+/// let removed = SparseMap::new();
+/// unregister_map(removed);
+/// assert!(export_json(removed) == Err(MapError::DanglingTarget(removed)));
+/// assert!(export_json(MapId::MAX) == Err(MapError::DanglingTarget(MapId::MAX)));
+/// ```
+pub fn export_json(root: MapId) -> Result<String, MapError> {
+    let map = {
+        let maps = MAPS.read();
+        let map = maps.get(root).ok_or(MapError::DanglingTarget(root))?.read();
+        if map.map_id() != root {
+            return Err(MapError::DanglingTarget(root));
+        }
+
+        map.box_clone()
+    };
+    let map_tree = MapTree::capture(&*map).map_err(MapError::DanglingTarget)?;
+
+    Ok(serde_json::to_string(&map_tree).expect("a map tree is always representable as JSON"))
+}
+
+/// Imports a map tree from JSON in the format produced by [`export_json`](fn.export_json.html), and returns the `MapId` of its root.
+///
+/// Every map in the tree is registered with a fresh `MapId`, and all `Portal` and `SubMap` targets are rewired from their indices to those `MapId`s. Nothing is registered if the input is rejected.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new_room(Size::new(4, 3));
+/// let b = SparseMap::new_room(Size::new(3, 3));
+/// link_maps(a, Position::new(3, 1), b, Position::new(0, 1), CardinalDirection::East);
+///
+/// let root = import_json(&export_json(a).unwrap()).unwrap();
+/// assert!(root != a);
+/// assert!(export_json(root).unwrap() == export_json(a).unwrap());
+/// {
+///     let maps = MAPS.read();
+///     let map = maps[root].read();
+///     assert!(map.to_ascii() == maps[a].read().to_ascii());
+///     let portal = map.get_portal_at(0).unwrap();
+///     assert!(portal.target() != b);
+///     assert!(maps[portal.target()].read().get_portal_at(0).unwrap().target() == root);
+/// }
+///
+/// let area = r#""area":{"position":{"x":0,"y":0},"size":{"width":1,"height":1}}"#;
+/// let json = format!(
+///     r#"{{"maps":[{{{},"tiles":[],"portals":[],"sub_maps":[{{"local_position":{{"x":0,"y":0}},"target":3}}]}}]}}"#,
+///     area
+/// );
+/// assert!(import_json(&json) == Err(ImportError::DanglingIndex(3)));
+///
+/// let json = format!(r#"{{"maps":[{{{},"tiles":[],"portals":[]}}]}}"#, area);
+/// match import_json(&json) {
+///     Err(ImportError::Format(message)) => assert!(message.contains("sub_maps")),
+///     _ => panic!("expected a missing field to be reported"),
+/// }
+///
+/// let json = format!(
+///     r#"{{"maps":[{{{},"tiles":[[0,0,"Lava"]],"portals":[],"sub_maps":[]}}]}}"#,
+///     area
+/// );
+/// match import_json(&json) {
+///     Err(ImportError::Format(message)) => assert!(message.contains("Lava")),
+///     _ => panic!("expected an unknown tile type to be reported"),
+/// }
+///
+/// assert!(matches!(import_json(r#"{"maps":["#), Err(ImportError::Syntax(_))));
+/// assert!(import_json(r#"{"maps":[]}"#) == Err(ImportError::DanglingIndex(0)));
+/// ```
+pub fn import_json(s: &str) -> Result<MapId, ImportError> {
+    let map_tree: MapTree = serde_json::from_str(s)?;
+    let root = map_tree.instantiate().map_err(ImportError::DanglingIndex)?;
+
+    Ok(register_map(root))
+}
@@ -0,0 +1,66 @@
+// External includes.
+use super::{MapId, MAPS};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Returns whether the areas of the registered maps `a` and `b` overlap, as per [`overlap_area`](fn.overlap_area.html).
+///
+/// See [`overlap_area`](fn.overlap_area.html) for an example.
+pub fn maps_overlap(a: MapId, b: MapId) -> bool {
+    overlap_area(a, b).is_some()
+}
+
+/// Returns the intersection of the areas of the registered maps `a` and `b`, including their `Position`s, or `None` if they do not overlap.
+///
+/// Areas overlap only if they share at least one tile; areas which merely touch, such that one ends on the column or row before the other begins, do not overlap, and neither does an area with a width or height of `0`. A map always overlaps itself, unless its area is empty.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let a = SparseMap::new_room(Size::new(4, 3));
+/// let b = SparseMap::new_room(Size::new(5, 5));
+/// let place = |map_id: MapId, position: Position| {
+///     *MAPS.read()[map_id].write().position_mut() = position;
+/// };
+///
+/// place(b, Position::new(10, 10));
+/// assert!(!maps_overlap(a, b));
+/// assert!(overlap_area(a, b) == None);
+///
+/// // Touching edges do not overlap.
+/// place(b, Position::new(4, 0));
+/// assert!(!maps_overlap(a, b));
+///
+/// place(b, Position::new(2, 1));
+/// assert!(maps_overlap(a, b) && maps_overlap(b, a));
+/// assert!(overlap_area(a, b) == Some(Area::new(Position::new(2, 1), Size::new(2, 2))));
+/// assert!(overlap_area(b, a) == overlap_area(a, b));
+/// assert!(overlap_area(a, a) == Some(Area::new(Position::zero(), Size::new(4, 3))));
+///
+/// let empty = SparseMap::new();
+/// assert!(!maps_overlap(a, empty));
+/// ```
+pub fn overlap_area(a: MapId, b: MapId) -> Option<Area> {
+    let maps = MAPS.read();
+    let area_a = *maps[a].read().area();
+    let area_b = *maps[b].read().area();
+
+    // The right and bottom are exclusive, so that touching and empty areas do not overlap.
+    let left = area_a.position().x().max(area_b.position().x());
+    let top = area_a.position().y().max(area_b.position().y());
+    let right = (area_a.position().x() + area_a.width() as i32)
+        .min(area_b.position().x() + area_b.width() as i32);
+    let bottom = (area_a.position().y() + area_a.height() as i32)
+        .min(area_b.position().y() + area_b.height() as i32);
+    if left >= right || top >= bottom {
+        return None;
+    }
+
+    Some(Area::new(
+        Position::new(left, top),
+        Size::new((right - left) as u32, (bottom - top) as u32),
+    ))
+}
@@ -0,0 +1,172 @@
+// External includes.
+use super::{Map, MapId, MAPS};
+
+// Standard includes.
+use std::ops::Deref;
+
+// Internal includes.
+use super::UnregisteredMap;
+
+/// An owned copy of a registered `Map`, as returned by [`try_get_map`](fn.try_get_map.html).
+///
+/// The copy is taken with `box_clone` under a read lock, which is released before it is returned; as such, a `MapHandle` holds no lock on `MAPS`, and may be kept for as long as is needed, or used while creating other maps. It does not reflect later changes to the registered map. It dereferences to `dyn Map` for reading; use [`into_inner`](#method.into_inner) to take the copy itself.
+pub struct MapHandle {
+    map: Box<dyn Map>,
+}
+
+impl MapHandle {
+    /// Returns the owned copy of the `Map`; changes to it do not affect the registered map.
+    pub fn into_inner(self) -> Box<dyn Map> {
+        self.map
+    }
+}
+
+impl Deref for MapHandle {
+    type Target = dyn Map;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.map
+    }
+}
+
+/// Returns whether a `Map` is currently registered under the given `MapId`.
+///
+/// A `MapId` which has been removed with [`unregister_map`](fn.unregister_map.html), or which has never been registered, returns `false`.
+///
+/// `invalidate_map` replaces a map with a placeholder whose `MapId` is always `0`; as such, a map with the `MapId` `0` which was removed with `invalidate_map`, rather than `unregister_map`, is still reported as registered.
+pub fn is_map_registered(map_id: MapId) -> bool {
+    MAPS.read()
+        .get(map_id)
+        .is_some_and(|map| map.read().map_id() == map_id)
+}
+
+/// Returns the number of `Map`s currently registered in `MAPS`.
+///
+/// Slots left by [`unregister_map`](fn.unregister_map.html) are not counted.
+///
+/// This takes a read lock on every map in `MAPS`, one at a time, to tell registered maps from those slots, and so must not be called while holding a write lock on any map; doing so deadlocks.
+pub fn registered_map_count() -> usize {
+    MAPS.read()
+        .iter()
+        .enumerate()
+        .filter(|(map_id, map)| map.read().map_id() == *map_id)
+        .count()
+}
+
+/// Returns the `MapId` of every `Map` currently registered in `MAPS`, in ascending order.
+///
+/// The `MapId`s are collected under a single read lock on `MAPS`, which is released before returning, so the caller may freely create or unregister maps while working through the returned `MapId`s. While collecting them, this also takes a read lock on every map in `MAPS`, one at a time, to skip slots left by `unregister_map`, and so must not be called while holding a write lock on any map; doing so deadlocks. As such, maps registered after the snapshot is taken will not be included, and maps in the snapshot may have been unregistered by the time they are used; these can be skipped by checking [`is_map_registered`](fn.is_map_registered.html).
+///
+/// ```
+/// # use dungen_minion_rooms::*;
+/// let map_ids = [SparseMap::new(), SparseMap::new(), DenseMap::new()];
+///
+/// assert!(registered_map_count() == 3);
+/// let snapshot = registered_map_ids();
+/// for map_id in map_ids.iter() {
+///     assert!(snapshot.contains(map_id));
+/// }
+///
+/// unregister_map(map_ids[1]);
+/// assert!(registered_map_count() == 2);
+/// assert!(!registered_map_ids().contains(&map_ids[1]));
+/// ```
+pub fn registered_map_ids() -> Vec<MapId> {
+    MAPS.read()
+        .iter()
+        .enumerate()
+        .filter(|(map_id, map)| map.read().map_id() == *map_id)
+        .map(|(map_id, _)| map_id)
+        .collect()
+}
+
+/// Returns a [`MapHandle`](struct.MapHandle.html) holding a copy of the `Map` registered under the given `MapId`, or `None` if no map is registered under it, such as for a stale `Portal` target.
+///
+/// Unlike indexing `MAPS`, this never panics, nor returns the tombstone placeholder left by [`unregister_map`](fn.unregister_map.html); it is the only supported lookup for a `MapId` which may have been unregistered. This takes a read lock on the map while copying it, and so must not be called while holding a write lock on it; the returned `MapHandle` holds no lock.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let map_id = SparseMap::new_room(Size::new(4, 3));
+/// let map = try_get_map(map_id).unwrap();
+/// assert!(map.map_id() == map_id);
+/// assert!(*map.size() == Size::new(4, 3));
+///
+/// // The handle holds no lock, so the map can still be written to.
+/// MAPS.read()[map_id]
+///     .write()
+///     .tile_type_at_local_set(Position::new(1, 1), TileType::Portal);
+/// assert!(map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Floor));
+///
+/// unregister_map(map_id);
+/// assert!(try_get_map(map_id).is_none());
+/// assert!(try_get_map(MapId::MAX).is_none());
+/// ```
+pub fn try_get_map(map_id: MapId) -> Option<MapHandle> {
+    let maps = MAPS.read();
+    let map = maps.get(map_id)?.read();
+    if map.map_id() != map_id {
+        return None;
+    }
+
+    Some(MapHandle {
+        map: map.box_clone(),
+    })
+}
+
+/// Removes the `Map` registered under the given `MapId` from `MAPS`, freeing its contents; returns whether a map was registered under that `MapId`.
+///
+/// The slot is not freed: the map is replaced with a tombstone placeholder, whose `map_id` is `MapId::MAX`, and the slot is never reused, so `MAPS.len()` never shrinks. Likewise, the `MapId` is not handed out again by `get_new_map_id`, so a stale `MapId` can never refer to a newer map. Indexing `MAPS` with a removed `MapId` does not panic, but returns the placeholder, whose tile reads all return `None`, and which discards all writes; [`try_get_map`](fn.try_get_map.html) is the only supported way to look up a map by a `MapId` which may have been unregistered.
+///
+/// # Safety expectations
+///
+/// Removing a map does not update `Portal`s or `SubMap`s which target it. Any that remain will resolve to the placeholder, so tiles will silently disappear from sub-maps, and portals will lead nowhere; remove or retarget them before, or soon after, unregistering their target.
+///
+/// This takes a write lock on the removed map, and so must not be called while holding a lock on that map.
+///
+/// ```
+/// # use dungen_minion_rooms::*;
+/// let map_ids: Vec<MapId> = (0..1000).map(|_| SparseMap::new()).collect();
+/// let count_registered = || map_ids.iter().filter(|map_id| is_map_registered(**map_id)).count();
+/// assert!(count_registered() == 1000);
+///
+/// for map_id in map_ids.iter().step_by(2) {
+///     assert!(unregister_map(*map_id));
+/// }
+///
+/// assert!(count_registered() == 500);
+/// assert!(registered_map_count() == 500);
+/// assert!(try_get_map(map_ids[0]).is_none());
+/// assert!(try_get_map(map_ids[1]).is_some());
+/// assert!(!unregister_map(map_ids[0]));
+/// assert!(!is_map_registered(map_ids[0]));
+/// assert!(is_map_registered(map_ids[1]));
+///
+/// // Reading through a sub-map which has been unregistered yields `None`, rather than panicking.
+/// # use dungen_minion_rooms::geometry::*;
+/// let sub_map_id = SparseMap::new();
+/// MAPS.read()[sub_map_id]
+///     .write()
+///     .tile_type_at_local_set(Position::zero(), TileType::Floor);
+/// let mut sparse_map = SparseMap::default();
+/// sparse_map.add_sub_map(Position::zero(), sub_map_id);
+/// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+///
+/// unregister_map(sub_map_id);
+/// assert!(sparse_map.tile_type_at_local(Position::zero()) == None);
+/// ```
+pub fn unregister_map(map_id: MapId) -> bool {
+    let maps = MAPS.read();
+    match maps.get(map_id) {
+        Some(map) => {
+            let mut map = map.write();
+            if map.map_id() != map_id {
+                return false;
+            }
+
+            *map = Box::new(UnregisteredMap::new());
+            true
+        }
+        None => false,
+    }
+}
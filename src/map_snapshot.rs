@@ -0,0 +1,85 @@
+// External includes.
+use super::{register_map, Map, MapId};
+
+// Standard includes.
+
+// Internal includes.
+use super::MapTree;
+
+/// An owned copy of a map, and of every map reachable from it through its sub-maps and `Portal`s, which does not depend upon `MAPS`.
+///
+/// Within a snapshot, `Portal` and `SubMap` targets refer to other maps in the same snapshot, rather than to `MapId`s, so that cycles are preserved; no `MapId` is meaningful until the snapshot is instantiated. As it holds no locks, nor `MapId`s, a `MapSnapshot` can be cached, compared, hashed, or sent to another thread.
+///
+/// Each map holds its area, the tiles stored directly in it, including `TileType::Void` tiles, its `Portal`s, and its sub-maps; sub-maps are captured by reference only, so their tiles are not baked into the maps which use them. For a `SparseMap`, each map also holds the `PortalKind` of each `Portal`, the tiles of each layer above layer `0`, its doors, and its `TileData`. Only a `SparseMap`'s out-of-bounds tile, tile priority, and tile observer are not captured.
+///
+/// Snapshots are equal if their maps hold all of the above equally, with the same `Portal`s and `SubMap`s in the same order, so maps which differ only in a layer, a door, or some tile data produce different snapshots.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let b = SparseMap::new_room(Size::new(3, 3));
+/// let c = SparseMap::new_filled(Size::new(1, 1), TileType::Portal);
+/// try_add_sub_map(b, Position::new(1, 1), c).unwrap();
+///
+/// let mut sparse_map = SparseMap::default();
+/// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 3)), TileType::Floor);
+/// sparse_map.add_portal(Position::new(3, 1), CardinalDirection::East, Position::new(0, 1), b);
+/// let snapshot = sparse_map.to_snapshot();
+/// assert!(snapshot.map_count() == 3);
+///
+/// let mut locked = sparse_map.clone();
+/// *locked.portal_kind_mut(0).unwrap() = PortalKind::Locked;
+/// assert!(locked.to_snapshot() != snapshot);
+/// let mut with_door = sparse_map.clone();
+/// with_door.set_door(Position::new(0, 1), false);
+/// let mut with_wall = sparse_map.clone();
+/// with_wall.tile_type_at_local_set(Position::new(0, 1), TileType::Wall);
+/// assert!(with_door.to_snapshot() != with_wall.to_snapshot());
+/// let mut layered = sparse_map.clone();
+/// layered.set_layer_tile(1, Position::new(1, 1), TileType::Wall);
+/// assert!(layered.to_snapshot() != snapshot);
+/// let mut trapped = sparse_map.clone();
+/// trapped.set_tile_data(Position::new(1, 1), TileData::new().with("damage", 5));
+/// assert!(trapped.to_snapshot() != snapshot);
+///
+/// let handle = std::thread::spawn(move || snapshot);
+/// let snapshot = handle.join().unwrap();
+/// assert!(snapshot == snapshot.clone());
+///
+/// let root = snapshot.clone().instantiate();
+/// let maps = MAPS.read();
+/// let map = maps[root].read();
+/// assert!(map.to_ascii() == sparse_map.to_ascii());
+/// let copy_of_b = maps[map.get_portal_at(0).unwrap().target()].read();
+/// assert!(copy_of_b.to_ascii() == maps[b].read().to_ascii());
+/// assert!(copy_of_b.sub_maps().into_iter().next().unwrap().value() != c);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MapSnapshot {
+    tree: MapTree,
+}
+
+impl MapSnapshot {
+    /// Captures `root` and every map reachable from it, returning the first `MapId` which could not be found in `MAPS` as an error.
+    pub(crate) fn capture(root: &dyn Map) -> Result<Self, MapId> {
+        MapTree::capture(root).map(|tree| Self { tree })
+    }
+
+    /// Registers a new `SparseMap` for every map in this snapshot, each with a fresh `MapId`, with all `Portal` and `SubMap` targets rewired to those `MapId`s, and returns the `MapId` of the root.
+    ///
+    /// See [`MapSnapshot`](struct.MapSnapshot.html) for an example.
+    pub fn instantiate(self) -> MapId {
+        let root = self
+            .tree
+            .instantiate()
+            .expect("a captured map tree only holds targets within itself");
+        register_map(root)
+    }
+
+    /// Returns the number of maps in this snapshot, including the root.
+    ///
+    /// See [`MapSnapshot`](struct.MapSnapshot.html) for an example.
+    pub fn map_count(&self) -> usize {
+        self.tree.map_count()
+    }
+}
@@ -0,0 +1,325 @@
+// External includes.
+use super::{get_new_map_id, register_map, Map, MapId, Portal, SubMap, TileType, MAPS};
+use crate::geometry::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Standard includes.
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+// Internal includes.
+use super::sparse_map_copy;
+use super::tile_type_index;
+use super::void_visible_cmp;
+use super::with_depth_limit;
+use super::PortalKind;
+use super::SparseMap;
+use super::TileData;
+#[cfg(feature = "serde")]
+use super::{AreaDef, CardinalDirectionDef, PositionDef, TileTypeDef};
+
+/// A single tile, serialized as an `[x, y, tile_type]` triple in local coordinates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct TileEntry(
+    Coord,
+    Coord,
+    #[cfg_attr(feature = "serde", serde(with = "TileTypeDef"))] TileType,
+);
+
+impl Hash for TileEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+        tile_type_index(self.2).hash(state);
+    }
+}
+
+/// The tiles of a `SparseMap` layer above layer `0`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct LayerEntry {
+    layer: u8,
+    tiles: Vec<TileEntry>,
+}
+
+/// A door of a `SparseMap`, serialized as an `[x, y, open]` triple in local coordinates.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct DoorEntry(Coord, Coord, bool);
+
+/// The `TileData` attached to a tile of a `SparseMap`, serialized as an `[x, y, tile_data]` triple in local coordinates.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct TileDataEntry(Coord, Coord, TileData);
+
+/// A `Portal` whose target is an index into `MapTree::maps`, along with its `PortalKind`, which is `PortalKind::Open` for maps other than `SparseMap`s, and for map trees saved without kinds.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct PortalEntry {
+    #[cfg_attr(feature = "serde", serde(with = "PositionDef"))]
+    local_position: Position,
+    #[cfg_attr(feature = "serde", serde(with = "CardinalDirectionDef"))]
+    portal_to_map_facing: CardinalDirection,
+    #[cfg_attr(feature = "serde", serde(with = "PositionDef"))]
+    portal_to_map_position: Position,
+    target: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    kind: PortalKind,
+}
+
+/// A `SubMap` whose target is an index into `MapTree::maps`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct SubMapEntry {
+    #[cfg_attr(feature = "serde", serde(with = "PositionDef"))]
+    local_position: Position,
+    target: usize,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+struct MapTreeEntry {
+    #[cfg_attr(feature = "serde", serde(with = "AreaDef"))]
+    area: Area,
+    tiles: Vec<TileEntry>,
+    portals: Vec<PortalEntry>,
+    sub_maps: Vec<SubMapEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    layers: Vec<LayerEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    doors: Vec<DoorEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    tile_data: Vec<TileDataEntry>,
+}
+
+/// A registry-independent table of every map reachable from a root map through its sub-maps and portals.
+///
+/// The root map is always at index `0`, and every `Portal` and `SubMap` target is stored as an index into `maps`, rather than as a `MapId`, so that cycles are preserved and fresh `MapId`s can be assigned on load.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub(crate) struct MapTree {
+    maps: Vec<MapTreeEntry>,
+}
+
+impl MapTree {
+    /// Captures `root` and every map reachable from it.
+    ///
+    /// Maps are read through the `Map` trait, with sub-maps left unresolved, so the tiles recorded for each map are those stored directly in it within its area, including `TileType::Void` tiles; the tiles of its sub-maps are recorded only with the sub-maps themselves. The `PortalKind`s of each `SparseMap` are recorded with its `Portal`s, and its layers, doors, and tile data alongside its tiles. The root is never looked up in `MAPS`, so it may be an unregistered map.
+    ///
+    /// Returns the first `MapId` that could not be found in `MAPS`, or has been unregistered, as an error.
+    pub(crate) fn capture(root: &dyn Map) -> Result<Self, MapId> {
+        let mut indices = HashMap::new();
+        let mut queue = VecDeque::new();
+        indices.insert(root.map_id(), 0);
+
+        let mut maps = vec![Self::capture_entry(root, &mut indices, &mut queue)];
+        while let Some(map_id) = queue.pop_front() {
+            let registry = MAPS.read();
+            let map = registry.get(map_id).ok_or(map_id)?.read();
+            if map.map_id() != map_id {
+                return Err(map_id);
+            }
+
+            maps.push(Self::capture_entry(&**map, &mut indices, &mut queue));
+        }
+
+        Ok(Self { maps })
+    }
+
+    fn capture_entry(
+        map: &dyn Map,
+        indices: &mut HashMap<MapId, usize>,
+        queue: &mut VecDeque<MapId>,
+    ) -> MapTreeEntry {
+        let mut index_of = |map_id: MapId| {
+            let next_index = indices.len();
+            *indices.entry(map_id).or_insert_with(|| {
+                queue.push_back(map_id);
+                next_index
+            })
+        };
+
+        // Only the map's own tiles are captured, including stored `Void` tiles; its sub-maps are captured by reference, so baking their tiles in would outlive moving or removing them.
+        let tiles = with_depth_limit(0, || {
+            let mut tiles = Vec::new();
+            for y in 0..map.height() as Coord {
+                for x in 0..map.width() as Coord {
+                    if let Some(tile_type) =
+                        map.tile_type_at_local_sort_by(Position::new(x, y), &void_visible_cmp)
+                    {
+                        tiles.push(TileEntry(x, y, tile_type));
+                    }
+                }
+            }
+            tiles
+        });
+
+        let sparse_map = sparse_map_copy(map);
+        let portals = map
+            .portals()
+            .into_iter()
+            .enumerate()
+            .map(|(index, portal)| PortalEntry {
+                local_position: *portal.local_position(),
+                portal_to_map_facing: *portal.portal_to_map_facing(),
+                portal_to_map_position: *portal.portal_to_map_position(),
+                target: index_of(portal.target()),
+                kind: sparse_map
+                    .as_ref()
+                    .and_then(|sparse_map| sparse_map.portal_kind(index))
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        let sub_maps = map
+            .sub_maps()
+            .into_iter()
+            .map(|sub_map| SubMapEntry {
+                local_position: *sub_map.local_position(),
+                target: index_of(sub_map.value()),
+            })
+            .collect();
+
+        let layers = sparse_map
+            .as_ref()
+            .map(|sparse_map| {
+                sparse_map
+                    .layers_sorted()
+                    .into_iter()
+                    .map(|(layer, tiles)| LayerEntry {
+                        layer,
+                        tiles: tiles
+                            .into_iter()
+                            .map(|(position, tile_type)| {
+                                TileEntry(position.x(), position.y(), tile_type)
+                            })
+                            .collect(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let tile_data = sparse_map
+            .as_ref()
+            .map(|sparse_map| {
+                sparse_map
+                    .tile_data_sorted()
+                    .into_iter()
+                    .map(|(position, tile_data)| {
+                        TileDataEntry(position.x(), position.y(), tile_data.clone())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let doors = sparse_map
+            .map(|sparse_map| {
+                sparse_map
+                    .doors_sorted()
+                    .into_iter()
+                    .map(|(position, open)| DoorEntry(position.x(), position.y(), open))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        MapTreeEntry {
+            area: *map.area(),
+            tiles,
+            portals,
+            sub_maps,
+            layers,
+            doors,
+            tile_data,
+        }
+    }
+
+    /// Returns the number of maps in the table, including the root.
+    pub(crate) fn map_count(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// Creates a `SparseMap` for every map in the table, each with a fresh `MapId`, and rewires all `Portal` and `SubMap` targets to those `MapId`s.
+    ///
+    /// Every map except the root is registered with `register_map`; the root is returned unregistered.
+    ///
+    /// Returns the first out-of-range target index as an error.
+    pub(crate) fn instantiate(self) -> Result<SparseMap, usize> {
+        let map_count = self.maps.len();
+        if map_count == 0 {
+            return Err(0);
+        }
+
+        for entry in self.maps.iter() {
+            let targets = entry
+                .portals
+                .iter()
+                .map(|portal| portal.target)
+                .chain(entry.sub_maps.iter().map(|sub_map| sub_map.target));
+            for target in targets {
+                if target >= map_count {
+                    return Err(target);
+                }
+            }
+        }
+
+        let map_ids: Vec<MapId> = (0..map_count).map(|_| get_new_map_id()).collect();
+        let mut sparse_maps = self
+            .maps
+            .into_iter()
+            .zip(map_ids.iter())
+            .map(|(entry, map_id)| {
+                let position = *entry.area.position();
+                let tiles = entry
+                    .tiles
+                    .into_iter()
+                    .map(|TileEntry(x, y, tile_type)| (Position::new(x, y) + position, tile_type))
+                    .collect();
+                let portals = entry
+                    .portals
+                    .into_iter()
+                    .map(|portal| {
+                        (
+                            Portal::new(
+                                portal.local_position,
+                                portal.portal_to_map_facing,
+                                portal.portal_to_map_position,
+                                map_ids[portal.target],
+                            ),
+                            portal.kind,
+                        )
+                    })
+                    .collect();
+                let sub_maps = entry
+                    .sub_maps
+                    .into_iter()
+                    .map(|sub_map| SubMap::new(sub_map.local_position, map_ids[sub_map.target]))
+                    .collect();
+
+                let mut sparse_map =
+                    SparseMap::from_parts(*map_id, entry.area, tiles, portals, sub_maps);
+                for LayerEntry { layer, tiles } in entry.layers {
+                    sparse_map.insert_layer(
+                        layer,
+                        tiles
+                            .into_iter()
+                            .map(|TileEntry(x, y, tile_type)| (Position::new(x, y), tile_type)),
+                    );
+                }
+                for DoorEntry(x, y, open) in entry.doors {
+                    sparse_map.insert_door(Position::new(x, y), open);
+                }
+                for TileDataEntry(x, y, tile_data) in entry.tile_data {
+                    sparse_map.set_tile_data(Position::new(x, y), tile_data);
+                }
+                sparse_map
+            })
+            .collect::<Vec<_>>();
+
+        let root = sparse_maps.remove(0);
+        for sparse_map in sparse_maps {
+            register_map(sparse_map);
+        }
+
+        Ok(root)
+    }
+}
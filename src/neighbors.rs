@@ -0,0 +1,235 @@
+// External includes.
+use super::{Map, TileType};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Returns `pos`, and its tile as resolved through `tile_type_at_local`, or `None` if it is outside of `map`'s area.
+fn neighbor<TMap>(map: &TMap, pos: Position) -> (Position, Option<TileType>)
+where
+    TMap: Map + ?Sized,
+{
+    if map.size().intersects_local_position(pos) {
+        (pos, map.tile_type_at_local(pos))
+    } else {
+        (pos, None)
+    }
+}
+
+/// Queries the tiles adjacent to a local `Position` of a [`Map`](trait.Map.html).
+///
+/// `Neighbors` is implemented for every `Map`, including the `Box<dyn Map>`s stored in `MAPS`.
+pub trait Neighbors: Map {
+    /// Returns the 4-connected neighbors of `pos`, as their local `Position`s and `TileType`s, in the order north, east, south, west.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`; neighbors outside of the `Map`'s area are `None`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::from_ascii("#.#\n.+.\n###", &|c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     '+' => Some(TileType::Portal),
+    ///     _ => None,
+    /// });
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    ///
+    /// let neighbors = map.neighbors4(Position::new(1, 1));
+    /// assert!(
+    ///     neighbors
+    ///         == [
+    ///             (Position::new(1, 0), Some(TileType::Floor)),
+    ///             (Position::new(2, 1), Some(TileType::Floor)),
+    ///             (Position::new(1, 2), Some(TileType::Wall)),
+    ///             (Position::new(0, 1), Some(TileType::Floor)),
+    ///         ]
+    /// );
+    ///
+    /// let neighbors = map.neighbors4(Position::new(0, 0));
+    /// assert!(
+    ///     neighbors
+    ///         == [
+    ///             (Position::new(0, -1), None),
+    ///             (Position::new(1, 0), Some(TileType::Floor)),
+    ///             (Position::new(0, 1), Some(TileType::Floor)),
+    ///             (Position::new(-1, 0), None),
+    ///         ]
+    /// );
+    /// ```
+    fn neighbors4(&self, pos: Position) -> [(Position, Option<TileType>); 4] {
+        [
+            Position::NORTH,
+            Position::EAST,
+            Position::SOUTH,
+            Position::WEST,
+        ]
+        .map(|offset| neighbor(self, pos + offset))
+    }
+
+    /// Returns the 8-connected neighbors of `pos`, as their local `Position`s and `TileType`s, clockwise from north: north, north-east, east, south-east, south, south-west, west, north-west.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`; neighbors outside of the `Map`'s area are `None`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::from_ascii("#.#\n.+.\n###", &|c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     '+' => Some(TileType::Portal),
+    ///     _ => None,
+    /// });
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    ///
+    /// let tile_types: Vec<Option<TileType>> = map
+    ///     .neighbors8(Position::new(1, 1))
+    ///     .iter()
+    ///     .map(|(_, tile_type)| *tile_type)
+    ///     .collect();
+    /// assert!(
+    ///     tile_types
+    ///         == vec![
+    ///             Some(TileType::Floor),
+    ///             Some(TileType::Wall),
+    ///             Some(TileType::Floor),
+    ///             Some(TileType::Wall),
+    ///             Some(TileType::Wall),
+    ///             Some(TileType::Wall),
+    ///             Some(TileType::Floor),
+    ///             Some(TileType::Wall),
+    ///         ]
+    /// );
+    ///
+    /// let neighbors = map.neighbors8(Position::new(2, 2));
+    /// assert!(neighbors[0] == (Position::new(2, 1), Some(TileType::Floor)));
+    /// assert!(neighbors[6] == (Position::new(1, 2), Some(TileType::Wall)));
+    /// assert!(neighbors[7] == (Position::new(1, 1), Some(TileType::Portal)));
+    /// assert!(neighbors[1..6].iter().all(|(_, tile_type)| *tile_type == None));
+    /// ```
+    fn neighbors8(&self, pos: Position) -> [(Position, Option<TileType>); 8] {
+        [
+            Position::NORTH,
+            Position::NORTH + Position::EAST,
+            Position::EAST,
+            Position::SOUTH + Position::EAST,
+            Position::SOUTH,
+            Position::SOUTH + Position::WEST,
+            Position::WEST,
+            Position::NORTH + Position::WEST,
+        ]
+        .map(|offset| neighbor(self, pos + offset))
+    }
+
+    /// Returns every local `Position` at a Chebyshev distance of exactly `radius` from `center`, clockwise from north, as per [`neighbors8`](#method.neighbors8); a `radius` of `0` returns `center` alone.
+    ///
+    /// The ring starts directly north of `center`, runs east to the north-east corner, and then around through the east, south, and west sides, ending just west of where it started; at a `radius` of `1`, this is the order of `neighbors8`. Positions outside of the `Map`'s area are left out, so a ring clipped by the edge of the `Map` has fewer than `8 * radius` positions, and may be empty.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::new_filled(Size::new(9, 9), TileType::Floor);
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    /// let center = Position::new(4, 4);
+    ///
+    /// assert!(map.ring_positions(center, 0) == vec![center]);
+    ///
+    /// let ring: Vec<Position> = map.neighbors8(center).iter().map(|(position, _)| *position).collect();
+    /// assert!(map.ring_positions(center, 1) == ring);
+    ///
+    /// let ring = map.ring_positions(center, 2);
+    /// assert!(ring.len() == 16);
+    /// assert!(
+    ///     ring == [
+    ///         (4, 2), (5, 2), (6, 2), (6, 3), (6, 4), (6, 5), (6, 6), (5, 6),
+    ///         (4, 6), (3, 6), (2, 6), (2, 5), (2, 4), (2, 3), (2, 2), (3, 2),
+    ///     ]
+    ///     .iter()
+    ///     .map(|(x, y)| Position::new(*x, *y))
+    ///     .collect::<Vec<Position>>()
+    /// );
+    ///
+    /// assert!(map.ring_positions(Position::zero(), 1) == vec![Position::new(1, 0), Position::new(1, 1), Position::new(0, 1)]);
+    /// assert!(map.ring_positions(center, 5).is_empty());
+    /// ```
+    fn ring_positions(&self, center: Position, radius: u32) -> Vec<Position> {
+        let radius = radius as i32;
+        if radius == 0 {
+            return if self.size().intersects_local_position(center) {
+                vec![center]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let (left, top) = (center.x() - radius, center.y() - radius);
+        let (right, bottom) = (center.x() + radius, center.y() + radius);
+        let north = (center.x()..right).map(|x| Position::new(x, top));
+        let east = (top..bottom).map(|y| Position::new(right, y));
+        let south = ((left + 1)..=right).rev().map(|x| Position::new(x, bottom));
+        let west = ((top + 1)..=bottom).rev().map(|y| Position::new(left, y));
+        let north_west = (left..center.x()).map(|x| Position::new(x, top));
+        north
+            .chain(east)
+            .chain(south)
+            .chain(west)
+            .chain(north_west)
+            .filter(|position| self.size().intersects_local_position(*position))
+            .collect()
+    }
+
+    /// Returns an iterator over every local `Position` within `max_radius` of `center`, in an outward spiral; that is, `center`, and then each ring around it, as per [`ring_positions`](#method.ring_positions), from a `radius` of `1` up to `max_radius`.
+    ///
+    /// Positions are ordered by their Chebyshev distance from `center`, and then clockwise from north, so that the first `Position` found to be suitable is also one of the nearest. As with `ring_positions`, positions outside of the `Map`'s area are left out; rings which lie wholly outside of the area are not visited, so a large `max_radius` costs no more than the area itself.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::new_room(Size::new(7, 7));
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    /// let center = Position::new(3, 3);
+    ///
+    /// assert!(
+    ///     map.spiral_iter(center, 2).take(10).collect::<Vec<Position>>()
+    ///         == [(3, 3), (3, 2), (4, 2), (4, 3), (4, 4), (3, 4), (2, 4), (2, 3), (2, 2), (3, 1)]
+    ///             .iter()
+    ///             .map(|(x, y)| Position::new(*x, *y))
+    ///             .collect::<Vec<Position>>()
+    /// );
+    /// assert!(map.spiral_iter(center, 2).count() == 25);
+    /// assert!(map.spiral_iter(center, 1000).count() == 49);
+    /// assert!(map.spiral_iter(Position::zero(), 1).count() == 4);
+    ///
+    /// // Find the nearest wall to the center.
+    /// let wall = map
+    ///     .spiral_iter(center, u32::MAX)
+    ///     .find(|position| map.tile_type_at_local(*position) == Some(TileType::Wall));
+    /// assert!(wall == Some(Position::new(3, 0)));
+    /// ```
+    fn spiral_iter(
+        &self,
+        center: Position,
+        max_radius: u32,
+    ) -> impl Iterator<Item = Position> + '_ {
+        // Beyond the farthest corner of the area, every ring is empty.
+        let farthest = [
+            center.x(),
+            self.size().width() as i32 - 1 - center.x(),
+            center.y(),
+            self.size().height() as i32 - 1 - center.y(),
+        ]
+        .iter()
+        .map(|distance| distance.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+        (0..=max_radius.min(farthest)).flat_map(move |radius| self.ring_positions(center, radius))
+    }
+}
+
+impl<TMap> Neighbors for TMap where TMap: Map + ?Sized {}
@@ -0,0 +1,115 @@
+// External includes.
+use super::{Map, TileType};
+use crate::geometry::*;
+
+// Standard includes.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+// Internal includes.
+
+/// Finds paths between local `Position`s of a [`Map`](trait.Map.html).
+///
+/// `Pathfinding` is implemented for every `Map`, including the `Box<dyn Map>`s stored in `MAPS`.
+pub trait Pathfinding: Map {
+    /// Returns the shortest 4-connected path from `start` to `goal`, including both, over tiles for which `passable` returns `true`, or `None` if there is no such path.
+    ///
+    /// The path is found with A*, using the Manhattan distance. Tiles are resolved through `tile_type_at_local`; positions with no tile, and positions outside of the `Map`'s area, are impassable. If `start` is `goal`, and passable, the path is that single position; if `start` or `goal` is impassable, there is no path.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::from_ascii(
+    ///     "#########\n#.......#\n#######.#\n#.......#\n#.#######\n#.......#\n#########\n ...",
+    ///     &|c| match c {
+    ///         '#' => Some(TileType::Wall),
+    ///         '.' => Some(TileType::Floor),
+    ///         _ => None,
+    ///     },
+    /// );
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    /// let passable = |tile_type| tile_type == TileType::Floor;
+    ///
+    /// let path = map.find_path(Position::new(1, 1), Position::new(7, 5), &passable).unwrap();
+    /// assert!(path.len() == 23);
+    /// assert!(path[0] == Position::new(1, 1) && path[22] == Position::new(7, 5));
+    /// assert!(path.windows(2).all(|step| {
+    ///     let offset = step[1] - step[0];
+    ///     offset.x().abs() + offset.y().abs() == 1
+    /// }));
+    ///
+    /// let start = Position::new(3, 3);
+    /// assert!(map.find_path(start, start, &passable) == Some(vec![start]));
+    /// assert!(map.find_path(Position::new(0, 0), start, &passable) == None);
+    /// assert!(map.find_path(start, Position::new(2, 7), &passable) == None);
+    /// ```
+    fn find_path(
+        &self,
+        start: Position,
+        goal: Position,
+        passable: &dyn Fn(TileType) -> bool,
+    ) -> Option<Vec<Position>> {
+        let is_passable = |position: Position| {
+            self.size().intersects_local_position(position)
+                && self.tile_type_at_local(position).is_some_and(passable)
+        };
+        if !is_passable(start) || !is_passable(goal) {
+            return None;
+        }
+
+        let heuristic = |position: Position| {
+            let offset = goal - position;
+            offset.x().abs() + offset.y().abs()
+        };
+
+        let mut came_from = HashMap::<Position, Position>::new();
+        let mut costs = HashMap::<Position, i32>::new();
+        let mut open = BinaryHeap::new();
+        costs.insert(start, 0);
+        open.push(Reverse((heuristic(start), 0, start.x(), start.y())));
+        while let Some(Reverse((_, cost, x, y))) = open.pop() {
+            let position = Position::new(x, y);
+            if position == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while let Some(previous) = came_from.get(&current) {
+                    path.push(*previous);
+                    current = *previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            if costs.get(&position).is_some_and(|best| *best < cost) {
+                continue;
+            }
+
+            for offset in [
+                Position::NORTH,
+                Position::EAST,
+                Position::SOUTH,
+                Position::WEST,
+            ] {
+                let next = position + offset;
+                let next_cost = cost + 1;
+                if costs.get(&next).is_some_and(|best| *best <= next_cost) || !is_passable(next) {
+                    continue;
+                }
+
+                costs.insert(next, next_cost);
+                came_from.insert(next, position);
+                open.push(Reverse((
+                    next_cost + heuristic(next),
+                    next_cost,
+                    next.x(),
+                    next.y(),
+                )));
+            }
+        }
+
+        None
+    }
+}
+
+impl<TMap> Pathfinding for TMap where TMap: Map + ?Sized {}
@@ -0,0 +1,26 @@
+// External includes.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Standard includes.
+
+// Internal includes.
+
+/// What kind of passage a `Portal` represents, for games which distinguish, for example, locked doors from open passages.
+///
+/// As `Portal` is defined by `dungen_minion_rooms_abstract`, it cannot carry a kind itself; instead, a [`SparseMap`](struct.SparseMap.html) stores a `PortalKind` alongside each of its `Portal`s, by index. See [`SparseMap::add_portal_with_kind`](struct.SparseMap.html#method.add_portal_with_kind).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum PortalKind {
+    /// An open passage, which can be crossed freely in either direction. This is the kind given to `Portal`s added with `add_portal`.
+    #[default]
+    Open,
+    /// A locked door, which must be unlocked before it can be crossed.
+    Locked,
+    /// A passage which can only be crossed from its map to its target.
+    OneWay,
+    /// A flight of stairs, leading up or down to its target.
+    Stairs,
+    /// A game-specific kind, identified by its tag.
+    Tagged(u32),
+}
@@ -0,0 +1,94 @@
+// External includes.
+use super::{MapId, Portal, PortalCollection};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Finds the [`Portal`](struct.Portal.html)s of a [`PortalCollection`](trait.PortalCollection.html) by their local `Position`.
+///
+/// `PortalLookup` is implemented for every `PortalCollection`, including the `Box<dyn Map>`s stored in `MAPS`.
+pub trait PortalLookup: PortalCollection {
+    /// Returns the first `Portal` whose local `Position` is `local_position`, or `None` if there is no such `Portal`.
+    ///
+    /// Multiple `Portal`s may share a tile; in that case, the first added wins. Use [`portals_at`](#method.portals_at) to find all of them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let north = SparseMap::new();
+    /// let east = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portal(Position::new(2, 0), CardinalDirection::North, Position::zero(), north);
+    /// sparse_map.add_portal(Position::new(4, 2), CardinalDirection::East, Position::zero(), east);
+    ///
+    /// assert!(sparse_map.portal_at(Position::new(2, 0)).unwrap().target() == north);
+    /// assert!(sparse_map.portal_at(Position::new(4, 2)).unwrap().target() == east);
+    /// assert!(sparse_map.portal_at(Position::new(1, 1)).is_none());
+    ///
+    /// sparse_map.add_portal(Position::new(2, 0), CardinalDirection::North, Position::zero(), east);
+    /// assert!(sparse_map.portal_at(Position::new(2, 0)).unwrap().target() == north);
+    /// let targets: Vec<MapId> = sparse_map
+    ///     .portals_at(Position::new(2, 0))
+    ///     .iter()
+    ///     .map(|portal| portal.target())
+    ///     .collect();
+    /// assert!(targets == vec![north, east]);
+    /// assert!(sparse_map.portals_at(Position::new(1, 1)).is_empty());
+    /// ```
+    fn portal_at(&self, local_position: Position) -> Option<&Portal> {
+        (0..self.portal_count())
+            .filter_map(|index| self.get_portal_at(index))
+            .find(|portal| *portal.local_position() == local_position)
+    }
+
+    /// Returns the local `Position`, facing, and target `MapId` of every `Portal`, in the order they were added.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let targets = [SparseMap::new(), SparseMap::new(), SparseMap::new()];
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portal(Position::new(2, 0), CardinalDirection::North, Position::zero(), targets[0]);
+    /// sparse_map.add_portal(Position::new(4, 2), CardinalDirection::East, Position::zero(), targets[1]);
+    /// sparse_map.add_portal(Position::new(0, 2), CardinalDirection::West, Position::zero(), targets[2]);
+    ///
+    /// assert!(
+    ///     sparse_map.portal_positions()
+    ///         == vec![
+    ///             (Position::new(2, 0), CardinalDirection::North, targets[0]),
+    ///             (Position::new(4, 2), CardinalDirection::East, targets[1]),
+    ///             (Position::new(0, 2), CardinalDirection::West, targets[2]),
+    ///         ]
+    /// );
+    /// assert!(SparseMap::default().portal_positions().is_empty());
+    /// ```
+    fn portal_positions(&self) -> Vec<(Position, CardinalDirection, MapId)> {
+        (0..self.portal_count())
+            .filter_map(|index| self.get_portal_at(index))
+            .map(|portal| {
+                (
+                    *portal.local_position(),
+                    *portal.portal_to_map_facing(),
+                    portal.target(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns every `Portal` whose local `Position` is `local_position`, in the order they were added.
+    ///
+    /// See [`portal_at`](#method.portal_at) for an example.
+    fn portals_at(&self, local_position: Position) -> Vec<&Portal> {
+        (0..self.portal_count())
+            .filter_map(|index| self.get_portal_at(index))
+            .filter(|portal| *portal.local_position() == local_position)
+            .collect()
+    }
+}
+
+impl<TPortalCollection> PortalLookup for TPortalCollection where
+    TPortalCollection: PortalCollection + ?Sized
+{
+}
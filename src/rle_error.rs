@@ -0,0 +1,44 @@
+// External includes.
+
+// Standard includes.
+use std::fmt;
+
+// Internal includes.
+
+/// The reasons [`SparseMap::from_rle`](struct.SparseMap.html#method.from_rle) can fail.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RleError {
+    /// The input does not match the format produced by [`SparseMap::to_rle`](struct.SparseMap.html#method.to_rle), such as when the header is missing, a field is not an integer, or a tile character is unknown; holds a description of the mismatch.
+    Format(String),
+    /// The header gives a negative width or height, or an area with more tiles than can be counted; holds the width and height as given.
+    InvalidSize(i64, i64),
+    /// The runs do not cover the area exactly; holds the number of tiles the runs cover, or `None` if that cannot be counted, and the number of tiles in the area.
+    RunLength(Option<usize>, usize),
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RleError::Format(message) => write!(f, "invalid RLE: {}", message),
+            RleError::InvalidSize(width, height) => {
+                write!(
+                    f,
+                    "an RLE area of {} by {} tiles is not valid",
+                    width, height
+                )
+            }
+            RleError::RunLength(Some(covered), tile_count) => write!(
+                f,
+                "the RLE runs cover {} of the {} tiles of the area",
+                covered, tile_count
+            ),
+            RleError::RunLength(None, tile_count) => write!(
+                f,
+                "the RLE runs cover more than the {} tiles of the area",
+                tile_count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
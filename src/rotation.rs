@@ -0,0 +1,52 @@
+// External includes.
+use super::{MapId, SubMap, MAPS};
+use crate::geometry::*;
+
+// Standard includes.
+use std::collections::HashMap;
+
+// Internal includes.
+
+/// Returns the offset which brings the local `Position`s of an area of the given `Size` back to non-negative coordinates after being multiplied by `rotation`.
+pub(crate) fn rotation_adjustment(size: Size, rotation: CardinalRotation) -> Position {
+    let right = (size.width() as i32 - 1).max(0);
+    let bottom = (size.height() as i32 - 1).max(0);
+    match rotation {
+        CardinalRotation::None => Position::new(0, 0),
+        CardinalRotation::Right90 => Position::new(0, right),
+        CardinalRotation::Full180 => Position::new(right, bottom),
+        CardinalRotation::Left90 => Position::new(bottom, 0),
+    }
+}
+
+/// Rotates the contents of every sub-map, and moves each so that it keeps its place relative to the tiles of a parent map rotated from `old_position` to `new_position`, by `rotation`, with the parent's `adjust_position`.
+///
+/// A sub-map which appears more than once is only rotated once. This takes a write lock on each sub-map.
+pub(crate) fn rotate_sub_maps(
+    sub_maps: &mut [SubMap],
+    old_position: Position,
+    new_position: Position,
+    adjust_position: Position,
+    rotation: CardinalRotation,
+) {
+    if sub_maps.is_empty() {
+        return;
+    }
+
+    let mut sub_map_adjustments = HashMap::<MapId, Position>::new();
+    let maps = MAPS.read();
+    for sub_map in sub_maps.iter_mut() {
+        let sub_map_adjustment = *sub_map_adjustments
+            .entry(sub_map.value())
+            .or_insert_with(|| {
+                let mut map = maps[sub_map.value()].write();
+                let sub_map_adjustment = rotation_adjustment(*map.size(), rotation);
+                map.rotate(rotation);
+                sub_map_adjustment
+            });
+
+        let offset = *sub_map.local_position() - old_position;
+        *sub_map.local_position_mut() =
+            new_position + offset * rotation + adjust_position - sub_map_adjustment;
+    }
+}
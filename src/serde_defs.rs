@@ -0,0 +1,218 @@
+// External includes.
+use super::{MapId, Portal, SubMap, TileType};
+use crate::geometry::*;
+use serde::{Deserialize, Serialize};
+
+// Standard includes.
+
+// Internal includes.
+
+/// A serde remote definition of [`TileType`](enum.TileType.html), for use with `#[serde(with = "TileTypeDef")]`.
+///
+/// Variants are externally tagged by name, so a `TileType` serializes as, for example, `"Floor"`; adding variants will not change how existing ones are read.
+///
+/// ```
+/// # use dungen_minion_rooms::*;
+/// for tile_type in [TileType::Void, TileType::Wall, TileType::Floor, TileType::Portal] {
+///     let mut serialized = Vec::new();
+///     TileTypeDef::serialize(&tile_type, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+///     let deserialized =
+///         TileTypeDef::deserialize(&mut serde_json::Deserializer::from_slice(&serialized)).unwrap();
+///     assert!(deserialized == tile_type);
+/// }
+///
+/// let mut serialized = Vec::new();
+/// TileTypeDef::serialize(&TileType::Floor, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+/// assert!(serialized == b"\"Floor\"");
+/// assert!(TileTypeDef::deserialize(&mut serde_json::Deserializer::from_str("\"Lava\"")).is_err());
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "TileType")]
+pub enum TileTypeDef {
+    /// As per `TileType::Void`.
+    Void,
+    /// As per `TileType::Wall`.
+    Wall,
+    /// As per `TileType::Floor`.
+    Floor,
+    /// As per `TileType::Portal`.
+    Portal,
+}
+
+/// A serde remote definition of [`CardinalDirection`](geometry/enum.CardinalDirection.html), for use with `#[serde(with = "CardinalDirectionDef")]`.
+///
+/// Variants are externally tagged by name, so a `CardinalDirection` serializes as, for example, `"North"`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let mut serialized = Vec::new();
+/// CardinalDirectionDef::serialize(
+///     &CardinalDirection::West,
+///     &mut serde_json::Serializer::new(&mut serialized),
+/// )
+/// .unwrap();
+/// assert!(serialized == b"\"West\"");
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "CardinalDirection")]
+pub enum CardinalDirectionDef {
+    /// As per `CardinalDirection::North`.
+    North,
+    /// As per `CardinalDirection::East`.
+    East,
+    /// As per `CardinalDirection::South`.
+    South,
+    /// As per `CardinalDirection::West`.
+    West,
+}
+
+/// A serde remote definition of [`Position`](geometry/struct.Position.html), for use with `#[serde(with = "PositionDef")]`; it serializes as a struct of `x` and `y`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let mut serialized = Vec::new();
+/// PositionDef::serialize(&Position::new(3, -2), &mut serde_json::Serializer::new(&mut serialized))
+///     .unwrap();
+/// assert!(serialized == b"{\"x\":3,\"y\":-2}");
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Position")]
+pub struct PositionDef {
+    #[serde(getter = "Position::x")]
+    x: Coord,
+    #[serde(getter = "Position::y")]
+    y: Coord,
+}
+
+impl From<PositionDef> for Position {
+    fn from(value: PositionDef) -> Self {
+        Position::new(value.x, value.y)
+    }
+}
+
+/// A serde remote definition of [`Size`](geometry/struct.Size.html), for use with `#[serde(with = "SizeDef")]`; it serializes as a struct of `width` and `height`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let mut serialized = Vec::new();
+/// SizeDef::serialize(&Size::new(4, 3), &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+/// assert!(serialized == b"{\"width\":4,\"height\":3}");
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Size")]
+pub struct SizeDef {
+    #[serde(getter = "Size::width")]
+    width: Length,
+    #[serde(getter = "Size::height")]
+    height: Length,
+}
+
+impl From<SizeDef> for Size {
+    fn from(value: SizeDef) -> Self {
+        Size::new(value.width, value.height)
+    }
+}
+
+/// A serde remote definition of [`Area`](geometry/struct.Area.html), for use with `#[serde(with = "AreaDef")]`; it serializes as a struct of `position` and `size`.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let area = Area::new(Position::new(1, 2), Size::new(4, 3));
+/// let mut serialized = Vec::new();
+/// AreaDef::serialize(&area, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+/// let deserialized =
+///     AreaDef::deserialize(&mut serde_json::Deserializer::from_slice(&serialized)).unwrap();
+/// assert!(deserialized == area);
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Area")]
+pub struct AreaDef {
+    #[serde(getter = "Area::position", with = "PositionDef")]
+    position: Position,
+    #[serde(getter = "Area::size", with = "SizeDef")]
+    size: Size,
+}
+
+impl From<AreaDef> for Area {
+    fn from(value: AreaDef) -> Self {
+        Area::new(value.position, value.size)
+    }
+}
+
+/// A serde remote definition of [`Portal`](struct.Portal.html), for use with `#[serde(with = "PortalDef")]`.
+///
+/// It serializes as a struct of `local_position`, `portal_to_map_facing`, `portal_to_map_position`, and `target`, matching the `Portal`s of a serialized `SparseMap`, except that `target` is the raw `MapId`, rather than an index into a map tree; it is only meaningful while that `MapId` is registered.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let portal = Portal::new(Position::new(3, 1), CardinalDirection::East, Position::new(0, 2), 7);
+/// let mut serialized = Vec::new();
+/// PortalDef::serialize(&portal, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+/// let deserialized =
+///     PortalDef::deserialize(&mut serde_json::Deserializer::from_slice(&serialized)).unwrap();
+///
+/// assert!(*deserialized.local_position() == Position::new(3, 1));
+/// assert!(*deserialized.portal_to_map_facing() == CardinalDirection::East);
+/// assert!(*deserialized.portal_to_map_position() == Position::new(0, 2));
+/// assert!(deserialized.target() == 7);
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Portal")]
+pub struct PortalDef {
+    #[serde(getter = "Portal::local_position", with = "PositionDef")]
+    local_position: Position,
+    #[serde(getter = "Portal::portal_to_map_facing", with = "CardinalDirectionDef")]
+    portal_to_map_facing: CardinalDirection,
+    #[serde(getter = "Portal::portal_to_map_position", with = "PositionDef")]
+    portal_to_map_position: Position,
+    #[serde(getter = "Portal::target")]
+    target: MapId,
+}
+
+impl From<PortalDef> for Portal {
+    fn from(value: PortalDef) -> Self {
+        Portal::new(
+            value.local_position,
+            value.portal_to_map_facing,
+            value.portal_to_map_position,
+            value.target,
+        )
+    }
+}
+
+/// A serde remote definition of [`SubMap`](struct.SubMap.html), for use with `#[serde(with = "SubMapDef")]`.
+///
+/// It serializes as a struct of `local_position` and `target`, matching the `SubMap`s of a serialized `SparseMap`, except that `target` is the raw `MapId`, rather than an index into a map tree; it is only meaningful while that `MapId` is registered.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let sub_map = SubMap::new(Position::new(2, 5), 3);
+/// let mut serialized = Vec::new();
+/// SubMapDef::serialize(&sub_map, &mut serde_json::Serializer::new(&mut serialized)).unwrap();
+/// assert!(serialized == b"{\"local_position\":{\"x\":2,\"y\":5},\"target\":3}");
+///
+/// let deserialized =
+///     SubMapDef::deserialize(&mut serde_json::Deserializer::from_slice(&serialized)).unwrap();
+/// assert!(*deserialized.local_position() == Position::new(2, 5));
+/// assert!(deserialized.value() == 3);
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "SubMap")]
+pub struct SubMapDef {
+    #[serde(getter = "SubMap::local_position", with = "PositionDef")]
+    local_position: Position,
+    #[serde(getter = "SubMap::value")]
+    target: MapId,
+}
+
+impl From<SubMapDef> for SubMap {
+    fn from(value: SubMapDef) -> Self {
+        SubMap::new(value.local_position, value.target)
+    }
+}
@@ -6,40 +6,4347 @@ use super::{
 };
 use crate::geometry::*;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 // Standard includes.
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+// Internal includes.
+use super::is_map_registered;
+use super::sub_maps_reach;
+use super::CorridorKind;
+use super::DenseMap;
+use super::MapError;
+use super::MapSnapshot;
+#[cfg(feature = "serde")]
+use super::MapTree;
+use super::Neighbors;
+use super::PortalKind;
+use super::RleError;
+use super::TileData;
+use super::TileSource;
+use super::ToAscii;
+use super::UnregisteredMap;
+use super::XorShift;
+use super::{for_each_resolved_sub_map, with_depth_limit};
+use super::{rotate_sub_maps, rotation_adjustment};
+
+/// A map which stores its [`TileType`](enum.TileType.html) information in a `HashMap`, indexed by [`Position`](geometry/struct.Position.html).
+///
+/// The size of the `SparseMap` will expand based on the `Position` provided, as per the specification for [`Map`](trait.Map.html).
+///
+/// Its sub-maps and `Portal` targets are read, and written, through their locks in `MAPS`, as described under [Locking](index.html#locking).
+#[derive(Clone)]
+pub struct SparseMap {
+    map_id: MapId,
+    area: Area,
+    tiles: HashMap<Position, TileType>,
+    layers: BTreeMap<u8, HashMap<Position, TileType>>,
+    doors: HashMap<Position, bool>,
+    tile_data: HashMap<Position, TileData>,
+    portals: Vec<Portal>,
+    portal_kinds: Vec<PortalKind>,
+    sub_maps: Vec<SubMap>,
+    out_of_bounds_tile: Option<TileType>,
+    tile_priority: Option<TilePriority>,
+    tile_observer: Option<TileObserver>,
+}
+
+/// A callback for tiles set in a `SparseMap`, which is passed each tile's local `Position`, the `TileType` previously stored there, and the new `TileType`; see [`SparseMap::set_tile_observer`](struct.SparseMap.html#method.set_tile_observer).
+pub type TileObserver = Arc<dyn Fn(Position, Option<TileType>, TileType) + Send + Sync>;
+
+/// A `tile_type_at_local_sort_by` comparison, which can be shared between maps; see [`SparseMap::set_tile_priority`](struct.SparseMap.html#method.set_tile_priority).
+pub type TilePriority =
+    Arc<dyn Fn(&Option<TileType>, &Option<TileType>) -> std::cmp::Ordering + Send + Sync>;
+
+/// A `tile_type_at_local_sort_by` comparison which follows `TileTypeStandardCmp`, except that `TileType::Void` wins over `None`, so that `Void` tiles can be read.
+pub(crate) fn void_visible_cmp(
+    left: &Option<TileType>,
+    right: &Option<TileType>,
+) -> std::cmp::Ordering {
+    match (left, right) {
+        (None, None) => std::cmp::Ordering::Equal,
+        (None, Some(_)) => std::cmp::Ordering::Less,
+        (Some(_), None) => std::cmp::Ordering::Greater,
+        (Some(left), Some(right)) => TileTypeStandardCmp::cmp(right, left),
+    }
+}
+
+thread_local! {
+    /// Whether `sparse_map_copy` is waiting for a `SparseMap` to answer its call to `box_clone`.
+    static SPARSE_MAP_COPY_REQUESTED: Cell<bool> = const { Cell::new(false) };
+    /// The copy with which a `SparseMap` answered `sparse_map_copy`.
+    static SPARSE_MAP_COPY: RefCell<Option<SparseMap>> = const { RefCell::new(None) };
+}
+
+/// Returns a copy of `map` if it is a `SparseMap`, or `None` if it is any other kind of `Map`.
+///
+/// `Map` is defined by `dungen_minion_rooms_abstract`, and offers no way to downcast; instead, this calls `box_clone`, which a `SparseMap` answers by handing its copy over here, so that what only a `SparseMap` stores, such as its `PortalKind`s, can be read from behind a `dyn Map`.
+pub(crate) fn sparse_map_copy(map: &dyn Map) -> Option<SparseMap> {
+    SPARSE_MAP_COPY_REQUESTED.with(|requested| requested.set(true));
+    drop(map.box_clone());
+    SPARSE_MAP_COPY_REQUESTED.with(|requested| requested.set(false));
+    SPARSE_MAP_COPY.with(|copy| copy.borrow_mut().take())
+}
+
+/// Returns the `TileType` which a door is stored as: `Floor` when open, and `Wall` when closed.
+fn door_tile_type(open: bool) -> TileType {
+    if open {
+        TileType::Floor
+    } else {
+        TileType::Wall
+    }
+}
+
+/// Returns a stable index for each `TileType`, which does not implement `Hash`.
+pub(crate) fn tile_type_index(tile_type: TileType) -> u8 {
+    match tile_type {
+        TileType::Void => 0,
+        TileType::Wall => 1,
+        TileType::Floor => 2,
+        TileType::Portal => 3,
+    }
+}
+
+/// Returns the character by which `to_rle` encodes a stored tile, or the lack of one.
+fn rle_char(tile_type: Option<TileType>) -> char {
+    match tile_type {
+        None => '-',
+        Some(TileType::Void) => '_',
+        Some(TileType::Wall) => '#',
+        Some(TileType::Floor) => '.',
+        Some(TileType::Portal) => '+',
+    }
+}
+
+/// Returns the character by which `to_rle` encodes a `CardinalDirection`.
+fn rle_facing_char(facing: CardinalDirection) -> char {
+    match facing {
+        CardinalDirection::North => 'N',
+        CardinalDirection::East => 'E',
+        CardinalDirection::South => 'S',
+        CardinalDirection::West => 'W',
+    }
+}
+
+/// Returns the field, including its leading space, by which `to_rle` encodes a `PortalKind`; `PortalKind::Open` has none.
+fn rle_portal_kind(portal_kind: PortalKind) -> String {
+    match portal_kind {
+        PortalKind::Open => String::new(),
+        PortalKind::Locked => " locked".to_string(),
+        PortalKind::OneWay => " one_way".to_string(),
+        PortalKind::Stairs => " stairs".to_string(),
+        PortalKind::Tagged(tag) => format!(" tagged:{}", tag),
+    }
+}
+
+/// Returns the number of each `TileType` in `tile_types`, in the order of `tile_type_index`, leaving out those which do not appear.
+fn tile_histogram_of(tile_types: impl Iterator<Item = TileType>) -> Vec<(TileType, usize)> {
+    let mut counts = [0; 4];
+    for tile_type in tile_types {
+        counts[tile_type_index(tile_type) as usize] += 1;
+    }
+
+    [
+        TileType::Void,
+        TileType::Wall,
+        TileType::Floor,
+        TileType::Portal,
+    ]
+    .iter()
+    .copied()
+    .zip(counts)
+    .filter(|(_, count)| *count > 0)
+    .collect()
+}
+
+impl SparseMap {
+    /// Adds a `Portal` of the given [`PortalKind`](enum.PortalKind.html), as per `add_portal`, which adds `PortalKind::Open` `Portal`s.
+    ///
+    /// The kind is stored by index, alongside the `Portal`, and is kept with it by this `SparseMap`'s own methods, such as `remove_portal_at` and `extract_area`. It is not captured by serialization, nor by other maps which copy `Portal`s through `PortalCollection`, such as `DenseMap`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portal(Position::new(0, 1), CardinalDirection::West, Position::zero(), target);
+    /// sparse_map.add_portal_with_kind(
+    ///     Position::new(3, 1),
+    ///     CardinalDirection::East,
+    ///     Position::zero(),
+    ///     target,
+    ///     PortalKind::Locked,
+    /// );
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 1)) == Some(TileType::Portal));
+    /// assert!(sparse_map.portal_kind(0) == Some(PortalKind::Open));
+    /// assert!(sparse_map.portal_kind(1) == Some(PortalKind::Locked));
+    /// assert!(sparse_map.portal_kind(2) == None);
+    ///
+    /// *sparse_map.portal_kind_mut(1).unwrap() = PortalKind::Open;
+    /// sparse_map.remove_portal_at(0);
+    /// assert!(sparse_map.portal_kind(0) == Some(PortalKind::Open));
+    /// ```
+    pub fn add_portal_with_kind(
+        &mut self,
+        local_position: Position,
+        portal_to_map_facing: CardinalDirection,
+        portal_to_map_position: Position,
+        target: MapId,
+        portal_kind: PortalKind,
+    ) {
+        self.portals.push(Portal::new(
+            local_position,
+            portal_to_map_facing,
+            portal_to_map_position,
+            target,
+        ));
+        self.portal_kinds.push(portal_kind);
+        self.tile_type_at_local_set(local_position, TileType::Portal);
+    }
+
+    /// Adds every `Portal` in `portals`, in order, each given as its local `Position`, its facing, its end-point in its target, and its target, as per `add_portal`.
+    ///
+    /// This is equivalent to calling `add_portal` for each, so every tile is set to `TileType::Portal`, and every `Portal` is `PortalKind::Open`. As `PortalCollection` is defined in `dungen_minion_rooms_abstract`, this is provided by `SparseMap` itself, rather than by that trait.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let north = SparseMap::new();
+    /// let east = SparseMap::new();
+    /// let south = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portals(&[
+    ///     (Position::new(2, 0), CardinalDirection::North, Position::new(1, 3), north),
+    ///     (Position::new(4, 2), CardinalDirection::East, Position::new(0, 1), east),
+    ///     (Position::new(2, 4), CardinalDirection::South, Position::new(1, 0), south),
+    /// ]);
+    ///
+    /// assert!(sparse_map.portal_count() == 3);
+    /// let targets: Vec<MapId> = sparse_map.portals().into_iter().map(|portal| portal.target()).collect();
+    /// assert!(targets == vec![north, east, south]);
+    /// assert!(sparse_map.get_portal_at(1).unwrap().portal_to_map_position() == &Position::new(0, 1));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 4)) == Some(TileType::Portal));
+    /// assert!(sparse_map.portal_kind(2) == Some(PortalKind::Open));
+    /// ```
+    pub fn add_portals(&mut self, portals: &[(Position, CardinalDirection, Position, MapId)]) {
+        for (local_position, portal_to_map_facing, portal_to_map_position, target) in portals {
+            self.add_portal(
+                *local_position,
+                *portal_to_map_facing,
+                *portal_to_map_position,
+                *target,
+            );
+        }
+    }
+
+    /// Returns an approximate number of bytes used by this `SparseMap`, including the capacity of its tile storage, its layers, doors, and tile data, and its lists of `Portal`s and sub-maps.
+    ///
+    /// The estimate is approximate; it counts allocated capacity, rather than only what is in use, but not the allocator's own overhead, nor the memory used by sub-maps, which are separate maps in `MAPS`. It never shrinks as tiles are added.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let empty_bytes = sparse_map.approx_memory_bytes();
+    /// let mut bytes = empty_bytes;
+    /// for x in 0..100 {
+    ///     sparse_map.tile_type_at_local_set(Position::new(x, 0), TileType::Floor);
+    ///     assert!(sparse_map.approx_memory_bytes() >= bytes);
+    ///     bytes = sparse_map.approx_memory_bytes();
+    /// }
+    /// assert!(bytes > empty_bytes);
+    /// ```
+    pub fn approx_memory_bytes(&self) -> usize {
+        fn tiles_bytes(tiles: &HashMap<Position, TileType>) -> usize {
+            // Each entry is stored alongside a one-byte control tag.
+            tiles.capacity() * (std::mem::size_of::<(Position, TileType)>() + 1)
+        }
+
+        std::mem::size_of::<Self>()
+            + tiles_bytes(&self.tiles)
+            + self
+                .layers
+                .values()
+                .map(|layer| {
+                    std::mem::size_of::<(u8, HashMap<Position, TileType>)>() + tiles_bytes(layer)
+                })
+                .sum::<usize>()
+            + self.doors.capacity() * (std::mem::size_of::<(Position, bool)>() + 1)
+            + self.tile_data.capacity() * (std::mem::size_of::<(Position, TileData)>() + 1)
+            + self.portals.capacity() * std::mem::size_of::<Portal>()
+            + self.portal_kinds.capacity() * std::mem::size_of::<PortalKind>()
+            + self.sub_maps.capacity() * std::mem::size_of::<SubMap>()
+    }
+
+    /// Writes every tile of the `source` map into this `SparseMap`, offset by `at`; the `SparseMap` will expand to fit the tiles, as per `tile_type_at_local_set`.
+    ///
+    /// Every local `Position` within `source`'s area is read, resolving `source`'s sub-maps. Positions with no tile are skipped; `TileType::Void` tiles are written only if `overwrite_void` is `true`, so that they can be used to erase destination tiles.
+    ///
+    /// The tiles are read from `source` before any are written, so `source` may be a sub-map of this `SparseMap`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let source = SparseMap::new();
+    /// {
+    ///     let maps = MAPS.read();
+    ///     let mut source = maps[source].write();
+    ///     for y in 0..2 {
+    ///         for x in 0..2 {
+    ///             source.tile_type_at_local_set(Position::new(x, y), TileType::Floor);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(source, Position::new(5, 5), false);
+    ///
+    /// assert!(*sparse_map.size() == Size::new(7, 7));
+    /// for y in 0..7 {
+    ///     for x in 0..7 {
+    ///         let expected = if x >= 5 && y >= 5 { Some(TileType::Floor) } else { None };
+    ///         assert!(sparse_map.tile_type_at_local(Position::new(x, y)) == expected);
+    ///     }
+    /// }
+    ///
+    /// MAPS.read()[source]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::zero(), TileType::Void);
+    /// sparse_map.blit(source, Position::new(5, 5), false);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 5)) == Some(TileType::Floor));
+    /// sparse_map.blit(source, Position::new(5, 5), true);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 5)) == None);
+    /// assert!(!sparse_map.intersects_local_position(Position::new(5, 5)));
+    /// ```
+    pub fn blit(&mut self, source: MapId, at: Position, overwrite_void: bool) {
+        let mut source_tiles = Vec::new();
+        {
+            let maps = MAPS.read();
+            let source = maps[source].read();
+            for y in 0..source.height() as i32 {
+                for x in 0..source.width() as i32 {
+                    let position = Position::new(x, y);
+                    match source.tile_type_at_local_sort_by(position, &void_visible_cmp) {
+                        Some(TileType::Void) if !overwrite_void => {}
+                        Some(tile_type) => source_tiles.push((position, tile_type)),
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        for (position, tile_type) in source_tiles {
+            self.tile_type_at_local_set(at + position, tile_type);
+        }
+    }
+
+    /// Sets an L-shaped corridor of `floor` tiles, `width` tiles wide, from `from` to `to`; if they share a row or column, the corridor is straight.
+    ///
+    /// The corridor runs horizontally along the row of `from`, to the column of `to`, and then vertically, to `to`. Each leg is widened from its centre line towards the right, and bottom, so a leg along row `y` covers rows `y` to `y + width - 1`; a `width` of `0` carves nothing. The tiles are set with [`fill_area`](#method.fill_area), so the `SparseMap` expands to fit the corridor, and local `Position`s with a negative coordinate are ignored.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(20, 15)), TileType::Wall);
+    /// let from = Position::new(1, 1);
+    /// let to = Position::new(18, 13);
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    /// assert!(sparse_map.find_path(from, to, &is_floor).is_none());
+    ///
+    /// sparse_map.carve_corridor(from, to, TileType::Floor, 1);
+    /// assert!(sparse_map.count_tile_type(TileType::Floor) == 17 + 12 + 1);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(18, 1)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 13)) == Some(TileType::Wall));
+    /// let path = sparse_map.find_path(from, to, &is_floor).unwrap();
+    /// assert!(path.first() == Some(&from) && path.last() == Some(&to));
+    ///
+    /// sparse_map.carve_corridor(Position::new(0, 5), Position::new(3, 5), TileType::Floor, 2);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 6)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 7)) == Some(TileType::Wall));
+    ///
+    /// sparse_map.carve_corridor(Position::new(18, 13), Position::new(24, 16), TileType::Floor, 1);
+    /// assert!(*sparse_map.size() == Size::new(25, 17));
+    /// ```
+    pub fn carve_corridor(&mut self, from: Position, to: Position, floor: TileType, width: u32) {
+        if width == 0 {
+            return;
+        }
+
+        let horizontal = Area::new(
+            Position::new(from.x().min(to.x()), from.y()),
+            Size::new((from.x() - to.x()).unsigned_abs() + width, width),
+        );
+        let vertical = Area::new(
+            Position::new(to.x(), from.y().min(to.y())),
+            Size::new(width, (from.y() - to.y()).unsigned_abs() + width),
+        );
+        self.fill_area(horizontal, floor);
+        self.fill_area(vertical, floor);
+    }
+
+    /// Removes every tile, `Portal`, and sub-map from this `SparseMap`, and resets its area to a zero size at `Position::zero()`; its `MapId` is unchanged, so that it can be reused without registering a new map.
+    ///
+    /// The sub-maps themselves remain registered, and unchanged.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// let map_id = sparse_map.map_id();
+    /// sparse_map.fill_area(Area::new(Position::new(0, 0), Size::new(5, 5)), TileType::Floor);
+    /// sparse_map.add_portal(Position::new(4, 2), CardinalDirection::East, Position::zero(), target);
+    /// sparse_map.add_sub_map(Position::new(1, 1), target);
+    /// *sparse_map.position_mut() = Position::new(3, 3);
+    ///
+    /// sparse_map.clear();
+    /// assert!(sparse_map.tiles_iter().count() == 0);
+    /// assert!(sparse_map.portal_count() == 0);
+    /// assert!(sparse_map.sub_map_count() == 0);
+    /// assert!(*sparse_map.area() == Area::new(Position::zero(), Size::zero()));
+    /// assert!(sparse_map.map_id() == map_id);
+    ///
+    /// assert!(register_map(sparse_map) == map_id);
+    /// assert!(MAPS.read()[map_id].read().map_id() == map_id);
+    /// ```
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.layers.clear();
+        self.doors.clear();
+        self.tile_data.clear();
+        self.portals.clear();
+        self.portal_kinds.clear();
+        self.sub_maps.clear();
+        *self.area_mut() = Area::new(Position::zero(), Size::zero());
+    }
+
+    /// Removes every stored `TileType::Void` tile, and releases the spare capacity of the tile storage.
+    ///
+    /// As `TileTypeStandardCmp` ranks `None` above `TileType::Void`, reads through `tile_type_at_local`, and `intersects_local_position`, are unchanged. Only code which can see `Void` tiles is affected: `tiles_iter` no longer yields them, `blit`ting this `SparseMap` with `overwrite_void` no longer erases there, and a priority set with [`set_tile_priority`](#method.set_tile_priority) which ranks `Void` above `None` will read `None` instead.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(20, 20));
+    /// sparse_map.fill_area(area, TileType::Void);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(5, 5), TileType::Floor);
+    /// let before: Vec<_> = sparse_map.iter_area(area).collect();
+    /// assert!(sparse_map.tiles_iter().count() == 400);
+    ///
+    /// sparse_map.compact();
+    /// assert!(sparse_map.tiles_iter().count() == 77);
+    /// assert!(sparse_map.iter_area(area).collect::<Vec<_>>() == before);
+    /// assert!(*sparse_map.size() == Size::new(20, 20));
+    /// ```
+    pub fn compact(&mut self) {
+        self.tiles
+            .retain(|_, tile_type| *tile_type != TileType::Void);
+        self.tiles.shrink_to_fit();
+    }
+
+    /// Returns the tile at the given local `Position` of the top-most layer which holds one other than `TileType::Void`, from the highest layer down to layer `0`, or `None` if no layer does.
+    ///
+    /// Layer `0` is resolved through `tile_type_at_local`; see [`set_layer_tile`](#method.set_layer_tile) for how the layers are stored.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(3, 1)), TileType::Floor);
+    /// sparse_map.set_layer_tile(1, Position::new(1, 0), TileType::Wall);
+    /// sparse_map.set_layer_tile(1, Position::new(2, 0), TileType::Wall);
+    /// sparse_map.set_layer_tile(2, Position::new(2, 0), TileType::Void);
+    /// sparse_map.set_layer_tile(2, Position::new(0, 1), TileType::Void);
+    ///
+    /// assert!(sparse_map.composite_tile(Position::new(0, 0)) == Some(TileType::Floor));
+    /// assert!(sparse_map.composite_tile(Position::new(1, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.composite_tile(Position::new(2, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.composite_tile(Position::new(0, 1)) == None);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 0)) == Some(TileType::Floor));
+    /// ```
+    pub fn composite_tile(&self, pos: Position) -> Option<TileType> {
+        let key = pos + *self.position();
+        self.layers
+            .values()
+            .rev()
+            .filter_map(|tiles| tiles.get(&key).copied())
+            .chain(self.tile_type_at_local(pos))
+            .find(|tile_type| *tile_type != TileType::Void)
+    }
+
+    /// Returns a fingerprint of this `SparseMap`'s contents, for deduplicating identical layouts; it is not cryptographic.
+    ///
+    /// The fingerprint covers exactly the contents which [`eq`](#method.eq) compares: the area; the out-of-bounds tile; the `Portal`s, with their [`PortalKind`](enum.PortalKind.html)s, in any order; the tiles of every layer; the doors, and whether each is open; the [`TileData`](struct.TileData.html) at each `Position`; and the tile resolved at every local `Position` within the area. As such, it does not depend upon the `MapId`, the sub-maps, other than through the tiles they provide, the tile priority, or the tile observer, nor upon the order in which tiles or `Portal`s were added, and `SparseMap`s which are equal always have equal fingerprints. Fingerprints are stable within a single build of the crate, but should not be persisted across builds.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut first = SparseMap::default();
+    /// first.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Wall);
+    /// first.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// first.add_portal(Position::new(1, 0), CardinalDirection::North, Position::zero(), target);
+    /// first.add_portal(Position::new(1, 2), CardinalDirection::South, Position::zero(), target);
+    ///
+    /// let mut second = SparseMap::default();
+    /// second.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Wall);
+    /// second.add_portal(Position::new(1, 2), CardinalDirection::South, Position::zero(), target);
+    /// second.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// second.add_portal(Position::new(1, 0), CardinalDirection::North, Position::zero(), target);
+    /// assert!(first.content_hash() == second.content_hash());
+    ///
+    /// second.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    /// assert!(first.content_hash() != second.content_hash());
+    ///
+    /// // Every other field which `eq` compares is covered too.
+    /// let changes: [fn(&mut SparseMap); 4] = [
+    ///     |sparse_map| {
+    ///         sparse_map.set_layer_tile(1, Position::new(1, 1), TileType::Portal);
+    ///     },
+    ///     |sparse_map| {
+    ///         sparse_map.set_door(Position::new(1, 0), false);
+    ///     },
+    ///     |sparse_map| {
+    ///         sparse_map.set_tile_data(Position::new(1, 1), TileData::new().with("damage", 5));
+    ///     },
+    ///     |sparse_map| *sparse_map.portal_kind_mut(0).unwrap() = PortalKind::Locked,
+    /// ];
+    /// for change in changes.iter() {
+    ///     let mut changed = first.clone();
+    ///     change(&mut changed);
+    ///     assert!(changed != first);
+    ///     assert!(changed.content_hash() != first.content_hash());
+    /// }
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.area().hash(&mut hasher);
+        self.out_of_bounds_tile
+            .map(tile_type_index)
+            .hash(&mut hasher);
+
+        let mut portal_hashes: Vec<u64> = self
+            .portals
+            .iter()
+            .zip(self.portal_kinds.iter())
+            .map(|(portal, portal_kind)| {
+                let mut portal_hasher = DefaultHasher::new();
+                portal.local_position().hash(&mut portal_hasher);
+                portal.portal_to_map_facing().hash(&mut portal_hasher);
+                portal.portal_to_map_position().hash(&mut portal_hasher);
+                portal.target().hash(&mut portal_hasher);
+                portal_kind.hash(&mut portal_hasher);
+                portal_hasher.finish()
+            })
+            .collect();
+        portal_hashes.sort_unstable();
+        portal_hashes.hash(&mut hasher);
+
+        for (layer, tiles) in self.layers_sorted() {
+            let tiles: Vec<(Position, u8)> = tiles
+                .into_iter()
+                .map(|(position, tile_type)| (position, tile_type_index(tile_type)))
+                .collect();
+            (layer, tiles).hash(&mut hasher);
+        }
+
+        self.doors_sorted().hash(&mut hasher);
+
+        self.tile_data_sorted().hash(&mut hasher);
+
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                self.tile_type_at_local(Position::new(x, y))
+                    .map(tile_type_index)
+                    .hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Classifies every walkable local `Position` within this `SparseMap`'s area by the walkable tiles among its 4-connected neighbours, as a [`CorridorKind`](enum.CorridorKind.html), for example to decorate corridors.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, and neighbours outside of the area are not walkable. Positions which are not walkable are not included.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let to_tile_type = |c| match c {
+    ///     '.' => Some(TileType::Floor),
+    ///     _ => None,
+    /// };
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(
+    ///     SparseMap::from_ascii("  .  \n  .  \n.....\n  .  \n  .. ", &to_tile_type),
+    ///     Position::zero(),
+    ///     false,
+    /// );
+    ///
+    /// let topology = sparse_map.corridor_topology(&|tile_type| tile_type == TileType::Floor);
+    /// assert!(topology.len() == 10);
+    /// assert!(topology[&Position::new(2, 2)] == CorridorKind::Crossing);
+    /// for end in [Position::new(2, 0), Position::new(0, 2), Position::new(4, 2), Position::new(3, 4)].iter() {
+    ///     assert!(topology[end] == CorridorKind::DeadEnd);
+    /// }
+    /// for arm in [Position::new(2, 1), Position::new(1, 2), Position::new(3, 2), Position::new(2, 3)].iter() {
+    ///     assert!(topology[arm] == CorridorKind::Straight);
+    /// }
+    /// assert!(topology[&Position::new(2, 4)] == CorridorKind::Corner);
+    /// assert!(!topology.contains_key(&Position::new(0, 0)));
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 2), TileType::Wall);
+    /// let topology = sparse_map.corridor_topology(&|tile_type| tile_type == TileType::Floor);
+    /// assert!(topology[&Position::new(2, 2)] == CorridorKind::TJunction);
+    /// assert!(topology[&Position::new(0, 2)] == CorridorKind::Isolated);
+    /// ```
+    pub fn corridor_topology(
+        &self,
+        is_walkable: &dyn Fn(TileType) -> bool,
+    ) -> HashMap<Position, CorridorKind> {
+        let is_walkable_at = |position: Position| {
+            self.size().intersects_local_position(position)
+                && self.tile_type_at_local(position).is_some_and(is_walkable)
+        };
+
+        let mut output = HashMap::new();
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let position = Position::new(x, y);
+                if !is_walkable_at(position) {
+                    continue;
+                }
+
+                let [north, east, south, west] = [
+                    Position::NORTH,
+                    Position::EAST,
+                    Position::SOUTH,
+                    Position::WEST,
+                ]
+                .map(|offset| is_walkable_at(position + offset));
+                let kind = match [north, east, south, west]
+                    .iter()
+                    .filter(|open| **open)
+                    .count()
+                {
+                    0 => CorridorKind::Isolated,
+                    1 => CorridorKind::DeadEnd,
+                    2 if north == south => CorridorKind::Straight,
+                    2 => CorridorKind::Corner,
+                    3 => CorridorKind::TJunction,
+                    _ => CorridorKind::Crossing,
+                };
+                output.insert(position, kind);
+            }
+        }
+
+        output
+    }
+
+    /// Returns the number of tiles stored directly in this `SparseMap` which are `tile_type`.
+    ///
+    /// See [`count_tiles`](#method.count_tiles) for an example.
+    pub fn count_tile_type(&self, tile_type: TileType) -> usize {
+        self.count_tiles(&|other| other == tile_type)
+    }
+
+    /// Returns the number of tiles stored directly in this `SparseMap` for which `matches` returns `true`.
+    ///
+    /// Tiles provided by sub-maps are not counted, while stored `TileType::Void` tiles are; use [`count_tiles_including_sub_maps`](#method.count_tiles_including_sub_maps) to count the resolved tiles instead.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 3)), TileType::Floor);
+    /// sparse_map.fill_border(Area::new(Position::zero(), Size::new(4, 3)), TileType::Wall);
+    ///
+    /// assert!(sparse_map.count_tile_type(TileType::Floor) == 2);
+    /// assert!(sparse_map.count_tile_type(TileType::Wall) == 10);
+    /// assert!(sparse_map.count_tile_type(TileType::Portal) == 0);
+    /// assert!(sparse_map.count_tiles(&|tile_type| tile_type != TileType::Void) == 12);
+    /// ```
+    pub fn count_tiles(&self, matches: &dyn Fn(TileType) -> bool) -> usize {
+        self.tiles
+            .values()
+            .filter(|tile_type| matches(**tile_type))
+            .count()
+    }
+
+    /// Returns the number of local `Position`s within this `SparseMap`'s area whose tile, as resolved through `tile_type_at_local`, is one for which `matches` returns `true`.
+    ///
+    /// Tiles provided by sub-maps are counted, while positions that resolve to no tile, including those which store `TileType::Void`, are not.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new();
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(1, 0), TileType::Floor);
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 0), TileType::Wall);
+    /// sparse_map.add_sub_map(Position::new(1, 0), sub_map);
+    ///
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    /// assert!(sparse_map.count_tiles(&is_floor) == 1);
+    /// assert!(sparse_map.count_tiles_including_sub_maps(&is_floor) == 2);
+    /// ```
+    pub fn count_tiles_including_sub_maps(&self, matches: &dyn Fn(TileType) -> bool) -> usize {
+        (0..self.height() as i32)
+            .flat_map(|y| (0..self.width() as i32).map(move |x| Position::new(x, y)))
+            .filter_map(|position| self.tile_type_at_local(position))
+            .filter(|tile_type| matches(*tile_type))
+            .count()
+    }
+
+    /// Creates and registers a copy of this `SparseMap`, with a fresh `MapId` from `get_new_map_id`, and returns its `MapId`; this `SparseMap` is left unchanged.
+    ///
+    /// Every tile, layer, door, `Portal`, and piece of tile data is copied, so that the copy can be edited independently. Sub-maps are not: they are separate maps in `MAPS`, so the copy's sub-maps target the same maps as the original's, and changes to those maps show through both. Any tile priority is shared; the tile observer is not copied, so that tiles set in the copy are not reported to the original's observer. Unlike `box_clone`, which keeps this `SparseMap`'s `MapId`, the copy can be registered alongside the original.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(4, 3));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    ///
+    /// let copy = sparse_map.deep_clone();
+    /// let original = register_map(sparse_map);
+    /// assert!(copy != original);
+    ///
+    /// let maps = MAPS.read();
+    /// assert!(maps[copy].read().map_id() == copy);
+    /// maps[copy]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(1, 1), TileType::Portal);
+    /// assert!(maps[copy].read().to_ascii() == "####\n#+.#\n####");
+    /// assert!(maps[original].read().to_ascii() == "####\n#..#\n####");
+    /// drop(maps);
+    ///
+    /// // The copy shares sub-map targets, but not the tile observer.
+    /// # use std::sync::{Arc, Mutex};
+    /// let sub_map = SparseMap::new();
+    /// let observed = Arc::new(Mutex::new(0));
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    /// let counter = observed.clone();
+    /// sparse_map.set_tile_observer(Arc::new(move |_, _, _| *counter.lock().unwrap() += 1));
+    ///
+    /// let copy = sparse_map.deep_clone();
+    /// MAPS.read()[copy]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// assert!(*observed.lock().unwrap() == 0);
+    ///
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::zero(), TileType::Wall);
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    /// assert!(MAPS.read()[copy].read().tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    /// ```
+    pub fn deep_clone(&self) -> MapId {
+        let mut sparse_map = self.clone();
+        sparse_map.map_id = get_new_map_id();
+        sparse_map.tile_observer = None;
+        register_map(sparse_map)
+    }
+
+    /// Sets every local `Position` within a Chebyshev distance of `radius` of a `target` tile to `target`, growing the `SparseMap` to fit, as per `tile_type_at_local_set`.
+    ///
+    /// The `target` tiles are found through `tile_type_at_local` before any tile is written, so growth does not cascade within a single call. As a `SparseMap` expands only to the right and bottom, positions to the left of, or above, the area's origin are not written. This takes read locks on each sub-map, and then write locks to write the new tiles through to them, and so must not be called while holding a lock on any of them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    ///
+    /// sparse_map.dilate(TileType::Floor, 1);
+    /// assert!(*sparse_map.size() == Size::new(3, 3));
+    /// assert!(sparse_map.to_ascii() == "...\n...\n...");
+    ///
+    /// sparse_map.dilate(TileType::Floor, 2);
+    /// assert!(*sparse_map.size() == Size::new(5, 5));
+    /// assert!(sparse_map.count_tile_type(TileType::Floor) == 25);
+    /// ```
+    pub fn dilate(&mut self, target: TileType, radius: u32) {
+        let radius = radius as i32;
+        let sources: Vec<Position> = self
+            .iter_area(Area::new(Position::zero(), *self.size()))
+            .filter(|(_, tile_type)| *tile_type == Some(target))
+            .map(|(position, _)| position)
+            .collect();
+
+        let mut dilated = HashSet::new();
+        for source in sources {
+            for y in (source.y() - radius).max(0)..=source.y() + radius {
+                for x in (source.x() - radius).max(0)..=source.x() + radius {
+                    dilated.insert(Position::new(x, y));
+                }
+            }
+        }
+
+        for position in dilated {
+            if self.tile_type_at_local(position) != Some(target) {
+                self.tile_type_at_local_set(position, target);
+            }
+        }
+    }
+
+    /// Returns whether the door at the given local `Position` is open, or `None` if there is no door there.
+    ///
+    /// A door is set with [`set_door`](#method.set_door), and remains for as long as its tile is unchanged; if the tile stored there is replaced, or removed, there is no longer a door there.
+    ///
+    /// See [`set_door`](#method.set_door) for an example.
+    pub fn door_at(&self, pos: Position) -> Option<bool> {
+        let key = pos + *self.position();
+        let open = *self.doors.get(&key)?;
+        if self.tiles.get(&key).copied() == Some(door_tile_type(open)) {
+            Some(open)
+        } else {
+            None
+        }
+    }
+
+    /// Stores a door at the given local `Position`, without setting its tile, for restoring a door along with the tile already stored under it.
+    pub(crate) fn insert_door(&mut self, pos: Position, open: bool) {
+        self.doors.insert(pos + *self.position(), open);
+    }
+
+    /// Stores the given tiles, at local `Position`s, in `layer`, which is created even if there are none, without changing the area; for restoring layers of a map whose area is already known. Layer `0` is ignored, as it is this `SparseMap`'s own tiles.
+    pub(crate) fn insert_layer(
+        &mut self,
+        layer: u8,
+        tiles: impl IntoIterator<Item = (Position, TileType)>,
+    ) {
+        if layer == 0 {
+            return;
+        }
+
+        let self_position = *self.position();
+        self.layers.entry(layer).or_default().extend(
+            tiles
+                .into_iter()
+                .map(|(position, tile_type)| (position + self_position, tile_type)),
+        );
+    }
+
+    /// Returns the tiles of every layer above layer `0`, in order of layer, as their local `Position`s and `TileType`s, sorted by row, and then by column.
+    pub(crate) fn layers_sorted(&self) -> Vec<(u8, Vec<(Position, TileType)>)> {
+        let self_position = *self.position();
+        self.layers
+            .iter()
+            .map(|(layer, tiles)| {
+                let mut tiles: Vec<(Position, TileType)> = tiles
+                    .iter()
+                    .map(|(key, tile_type)| (*key - self_position, *tile_type))
+                    .collect();
+                tiles.sort_unstable_by_key(|(position, _)| (position.y(), position.x()));
+                (*layer, tiles)
+            })
+            .collect()
+    }
+
+    /// Returns all tile data, as its local `Position` and `TileData`, sorted by row, and then by column.
+    pub(crate) fn tile_data_sorted(&self) -> Vec<(Position, &TileData)> {
+        let self_position = *self.position();
+        let mut tile_data: Vec<(Position, &TileData)> = self
+            .tile_data
+            .iter()
+            .map(|(key, tile_data)| (*key - self_position, tile_data))
+            .collect();
+        tile_data.sort_unstable_by_key(|(position, _)| (position.y(), position.x()));
+        tile_data
+    }
+
+    /// Returns every door reported by [`door_at`](#method.door_at), as its local `Position` and whether it is open, sorted by row, and then by column.
+    pub(crate) fn doors_sorted(&self) -> Vec<(Position, bool)> {
+        let self_position = *self.position();
+        let mut doors: Vec<(Position, bool)> = self
+            .doors
+            .keys()
+            .filter_map(|key| {
+                let position = *key - self_position;
+                self.door_at(position).map(|open| (position, open))
+            })
+            .collect();
+        doors.sort_unstable_by_key(|(position, _)| (position.y(), position.x()));
+        doors
+    }
+
+    /// Returns an iterator over the local `Position`s on the outermost ring of this `SparseMap`'s area, in order of row, and then column.
+    ///
+    /// The positions are yielded regardless of the tiles there; use [`floor_edge_positions`](#method.floor_edge_positions) to find the edges of the floor instead.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 4)), TileType::Floor);
+    ///
+    /// let edges: Vec<Position> = sparse_map.edge_positions().collect();
+    /// assert!(edges.len() == 12);
+    /// assert!(
+    ///     edges
+    ///         == vec![
+    ///             Position::new(0, 0),
+    ///             Position::new(1, 0),
+    ///             Position::new(2, 0),
+    ///             Position::new(3, 0),
+    ///             Position::new(0, 1),
+    ///             Position::new(3, 1),
+    ///             Position::new(0, 2),
+    ///             Position::new(3, 2),
+    ///             Position::new(0, 3),
+    ///             Position::new(1, 3),
+    ///             Position::new(2, 3),
+    ///             Position::new(3, 3),
+    ///         ]
+    /// );
+    /// assert!(SparseMap::default().edge_positions().count() == 0);
+    /// ```
+    pub fn edge_positions(&self) -> impl Iterator<Item = Position> {
+        let right = self.width() as i32 - 1;
+        let bottom = self.height() as i32 - 1;
+        (0..=bottom).flat_map(move |y| {
+            (0..=right)
+                .filter(move |x| y == 0 || y == bottom || *x == 0 || *x == right)
+                .map(move |x| Position::new(x, y))
+        })
+    }
+
+    /// Returns every region of floor which is sealed off, as per [`walkable_regions`](#method.walkable_regions); that is, every region whose 4-connected neighbours are all walls, and which contains neither a `TileType::Portal` tile nor a `Portal`.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`. A region which borders a local `Position` with no tile, a tile which is neither floor nor wall, or the edge of the area, is open, and is not returned. Such sealed regions can only be reached by breaking through a wall, such as for a vault behind a secret door. The regions are ordered, and their `Position`s sorted, as per `walkable_regions`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let to_tile_type = |c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     '+' => Some(TileType::Portal),
+    ///     _ => None,
+    /// };
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(
+    ///     SparseMap::from_ascii("##########\n#..#.#####\n#..#+#....\n##########", &to_tile_type),
+    ///     Position::zero(),
+    ///     false,
+    /// );
+    /// let is_floor = |tile_type| tile_type == TileType::Floor || tile_type == TileType::Portal;
+    /// let is_wall = |tile_type| tile_type == TileType::Wall;
+    ///
+    /// let regions = sparse_map.enclosed_regions(&is_floor, &is_wall);
+    /// assert!(
+    ///     regions
+    ///         == vec![vec![
+    ///             Position::new(1, 1),
+    ///             Position::new(2, 1),
+    ///             Position::new(1, 2),
+    ///             Position::new(2, 2),
+    ///         ]]
+    /// );
+    ///
+    /// // Open the vault onto the corridor.
+    /// sparse_map.tile_type_at_local_set(Position::new(3, 2), TileType::Void);
+    /// assert!(sparse_map.enclosed_regions(&is_floor, &is_wall).is_empty());
+    /// ```
+    pub fn enclosed_regions(
+        &self,
+        is_floor: &dyn Fn(TileType) -> bool,
+        is_wall: &dyn Fn(TileType) -> bool,
+    ) -> Vec<Vec<Position>> {
+        let portal_positions: HashSet<Position> = self
+            .portals
+            .iter()
+            .map(|portal| *portal.local_position())
+            .collect();
+
+        self.walkable_regions(is_floor)
+            .into_iter()
+            .filter(|region| {
+                let positions: HashSet<Position> = region.iter().copied().collect();
+                region.iter().all(|position| {
+                    !portal_positions.contains(position)
+                        && self.tile_type_at_local(*position) != Some(TileType::Portal)
+                        && [
+                            Position::NORTH,
+                            Position::EAST,
+                            Position::SOUTH,
+                            Position::WEST,
+                        ]
+                        .iter()
+                        .map(|offset| *position + *offset)
+                        .filter(|neighbour| !positions.contains(neighbour))
+                        .all(|neighbour| {
+                            self.size().intersects_local_position(neighbour)
+                                && self.tile_type_at_local(neighbour).is_some_and(is_wall)
+                        })
+                })
+            })
+            .collect()
+    }
+
+    /// Removes every `target` tile which has a tile other than `target` within a Chebyshev distance of `radius`; local `Position`s outside of the area, or with no tile, count as other than `target`.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, and all are checked before any is removed, so erosion does not cascade within a single call. Only the tiles stored directly in this `SparseMap` are removed, so `target` tiles provided by sub-maps remain, as does the area.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// sparse_map.dilate(TileType::Floor, 1);
+    ///
+    /// sparse_map.erode(TileType::Floor, 1);
+    /// assert!(*sparse_map.size() == Size::new(3, 3));
+    /// assert!(sparse_map.count_tile_type(TileType::Floor) == 1);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Floor));
+    ///
+    /// sparse_map.erode(TileType::Floor, 1);
+    /// assert!(sparse_map.count_tiles(&|_| true) == 0);
+    /// ```
+    pub fn erode(&mut self, target: TileType, radius: u32) {
+        let radius = radius as i32;
+        let eroded: Vec<Position> = self
+            .iter_area(Area::new(Position::zero(), *self.size()))
+            .filter(|(position, tile_type)| {
+                *tile_type == Some(target)
+                    && (-radius..=radius).any(|y| {
+                        (-radius..=radius).any(|x| {
+                            let neighbor = *position + Position::new(x, y);
+                            !self.size().intersects_local_position(neighbor)
+                                || self.tile_type_at_local(neighbor) != Some(target)
+                        })
+                    })
+            })
+            .map(|(position, _)| position)
+            .collect();
+
+        for position in eroded {
+            self.tile_type_at_local_remove(position);
+        }
+    }
+
+    /// Creates and registers a new `SparseMap` from the given local `Area` of this `SparseMap`, and returns its `MapId`.
+    ///
+    /// The new `SparseMap` has the `Size` of `area`, and its local coordinates start at `area`'s `Position`. Every tile stored directly in this `SparseMap` within `area` is copied to the new map, as is every `Portal` whose local `Position` is within `area`; `Portal`s elsewhere are dropped.
+    ///
+    /// Sub-maps which lie entirely within `area` are referenced by the new map, at their adjusted local `Position`s. Sub-maps which lie partially or entirely outside of `area` are dropped, along with any tiles they provide.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let contained_sub_map = SparseMap::new();
+    /// let partial_sub_map = SparseMap::new();
+    /// {
+    ///     let maps = MAPS.read();
+    ///     maps[contained_sub_map].write().tile_type_at_local_set(Position::zero(), TileType::Floor);
+    ///     maps[partial_sub_map].write().tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// }
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// for y in 0..9 {
+    ///     for x in 0..9 {
+    ///         let tile_type = if (x + y) % 2 == 0 { TileType::Floor } else { TileType::Wall };
+    ///         sparse_map.tile_type_at_local_set(Position::new(x, y), tile_type);
+    ///     }
+    /// }
+    /// sparse_map.add_portal(Position::new(4, 4), CardinalDirection::North, Position::zero(), target);
+    /// sparse_map.add_portal(Position::new(0, 0), CardinalDirection::South, Position::zero(), target);
+    /// sparse_map.add_sub_map(Position::new(3, 3), contained_sub_map);
+    /// sparse_map.add_sub_map(Position::new(5, 5), partial_sub_map);
+    ///
+    /// let extracted_id = sparse_map.extract_area(Area::new(Position::new(3, 3), Size::new(3, 3)));
+    ///
+    /// let maps = MAPS.read();
+    /// let extracted = maps[extracted_id].read();
+    /// assert!(*extracted.position() == Position::zero());
+    /// assert!(*extracted.size() == Size::new(3, 3));
+    /// for y in 0..3 {
+    ///     for x in 0..3 {
+    ///         let position = Position::new(x, y);
+    ///         let expected = sparse_map.tile_type_at_local(position + Position::new(3, 3));
+    ///         assert!(extracted.tile_type_at_local(position) == expected);
+    ///     }
+    /// }
+    /// assert!(extracted.portal_count() == 1);
+    /// assert!(*extracted.get_portal_at(0).unwrap().local_position() == Position::new(1, 1));
+    /// assert!(extracted.sub_map_count() == 1);
+    /// assert!(extracted.get_sub_map_at(0).unwrap().value() == contained_sub_map);
+    /// assert!(*extracted.get_sub_map_at(0).unwrap().local_position() == Position::zero());
+    /// ```
+    pub fn extract_area(&self, area: Area) -> MapId {
+        let mut sparse_map = Self::default();
+        *sparse_map.size_mut() = *area.size();
+
+        let self_position = *self.position();
+        for (tile_position, tile_type) in self.tiles.iter() {
+            let tile_local_position = *tile_position - self_position;
+            if area.intersects_position(tile_local_position) {
+                sparse_map
+                    .tiles
+                    .insert(tile_local_position - *area.position(), *tile_type);
+            }
+        }
+
+        for (portal, portal_kind) in self.portals.iter().zip(self.portal_kinds.iter()) {
+            if area.intersects_position(*portal.local_position()) {
+                sparse_map.portals.push(Portal::new(
+                    *portal.local_position() - *area.position(),
+                    *portal.portal_to_map_facing(),
+                    *portal.portal_to_map_position(),
+                    portal.target(),
+                ));
+                sparse_map.portal_kinds.push(*portal_kind);
+            }
+        }
+
+        if !self.sub_maps.is_empty() {
+            let maps = MAPS.read();
+            for sub_map in self.sub_maps.iter() {
+                let sub_map_size = *maps[sub_map.value()].read().size();
+                let sub_map_position = self.sub_map_offset(sub_map);
+                let contained = sub_map_position.x() >= area.position().x()
+                    && sub_map_position.y() >= area.position().y()
+                    && sub_map_position.x() + sub_map_size.width() as i32
+                        <= area.position().x() + area.width() as i32
+                    && sub_map_position.y() + sub_map_size.height() as i32
+                        <= area.position().y() + area.height() as i32;
+                if contained {
+                    sparse_map.sub_maps.push(SubMap::new(
+                        sub_map_position - *area.position(),
+                        sub_map.value(),
+                    ));
+                }
+            }
+        }
+
+        register_map(sparse_map)
+    }
+
+    /// Returns every local `Position` that is 4-connected to `start` through tiles satisfying `matches`, including `start`, in breadth-first order.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, and only local `Position`s which intersect the `SparseMap`, as per `intersects_local_position`, are visited; as such, the fill never enters missing or `TileType::Void` tiles, nor leaves the `SparseMap`'s area. Whether it enters `TileType::Wall` tiles is up to `matches`.
+    ///
+    /// If the tile at `start` does not satisfy `matches`, the returned `Vec` is empty. The fill uses an explicit queue, rather than recursion, so large regions cannot overflow the stack.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// // Two 2x2 floor rooms, separated by a column of walls.
+    /// for y in 0..2 {
+    ///     for x in 0..5 {
+    ///         let tile_type = if x == 2 { TileType::Wall } else { TileType::Floor };
+    ///         sparse_map.tile_type_at_local_set(Position::new(x, y), tile_type);
+    ///     }
+    /// }
+    ///
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    /// let mut region = sparse_map.flood_fill(Position::new(0, 1), &is_floor);
+    /// region.sort_by_key(|position| (position.y(), position.x()));
+    /// assert!(
+    ///     region
+    ///         == vec![
+    ///             Position::new(0, 0),
+    ///             Position::new(1, 0),
+    ///             Position::new(0, 1),
+    ///             Position::new(1, 1),
+    ///         ]
+    /// );
+    ///
+    /// assert!(sparse_map.flood_fill(Position::new(4, 0), &is_floor).len() == 4);
+    /// assert!(sparse_map.flood_fill(Position::new(2, 0), &is_floor).is_empty());
+    /// assert!(sparse_map.flood_fill(Position::new(9, 9), &is_floor).is_empty());
+    /// ```
+    pub fn flood_fill(&self, start: Position, matches: &dyn Fn(TileType) -> bool) -> Vec<Position> {
+        let is_match = |position: Position| {
+            self.intersects_local_position(position)
+                && self.tile_type_at_local(position).is_some_and(matches)
+        };
+
+        let mut output = Vec::new();
+        if !is_match(start) {
+            return output;
+        }
+
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start);
+        queue.push_back(start);
+        while let Some(position) = queue.pop_front() {
+            output.push(position);
+            for offset in [
+                Position::NORTH,
+                Position::EAST,
+                Position::SOUTH,
+                Position::WEST,
+            ]
+            .iter()
+            {
+                let neighbour = position + *offset;
+                if !visited.contains(&neighbour) && is_match(neighbour) {
+                    visited.insert(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Returns an iterator over the local `Position`s of every `TileType::Floor` tile which is 4-connected to a tile which is not floor, in order of row, and then column.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`. A neighbor is not floor if it is `TileType::Wall` or `TileType::Void`, has no tile, or lies outside of the `SparseMap`'s area; `TileType::Portal` neighbors do not make an edge.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::from_ascii("#####\n#...#\n#...+\n#.. #\n.####", &|c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     '+' => Some(TileType::Portal),
+    ///     _ => None,
+    /// });
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(map_id, Position::zero(), false);
+    ///
+    /// let edges: Vec<Position> = sparse_map.floor_edge_positions().collect();
+    /// assert!(
+    ///     edges
+    ///         == vec![
+    ///             Position::new(1, 1),
+    ///             Position::new(2, 1),
+    ///             Position::new(3, 1),
+    ///             Position::new(1, 2),
+    ///             Position::new(3, 2),
+    ///             Position::new(1, 3),
+    ///             Position::new(2, 3),
+    ///             Position::new(0, 4),
+    ///         ]
+    /// );
+    /// ```
+    pub fn floor_edge_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.height() as i32)
+            .flat_map(move |y| (0..self.width() as i32).map(move |x| Position::new(x, y)))
+            .filter(move |position| {
+                self.tile_type_at_local(*position) == Some(TileType::Floor)
+                    && self.neighbors4(*position).iter().any(|(_, tile_type)| {
+                        !matches!(tile_type, Some(TileType::Floor) | Some(TileType::Portal))
+                    })
+            })
+    }
+
+    /// Sets every local `Position` within `area` to `tile_type`; the `SparseMap` will expand to fit `area`, as per `tile_type_at_local_set`, which also ignores the positions of `area` with a negative coordinate. A zero-sized `area` does nothing.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(4, 4));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// assert!(*sparse_map.size() == Size::new(4, 4));
+    /// assert!(sparse_map.to_ascii() == "....\n....\n....\n....");
+    ///
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// assert!(sparse_map.to_ascii() == "####\n#..#\n#..#\n####");
+    ///
+    /// sparse_map.fill_area(Area::new(Position::new(1, 1), Size::zero()), TileType::Wall);
+    /// sparse_map.fill_border(Area::new(Position::new(1, 1), Size::new(0, 2)), TileType::Wall);
+    /// assert!(sparse_map.to_ascii() == "####\n#..#\n#..#\n####");
+    /// ```
+    pub fn fill_area(&mut self, area: Area, tile_type: TileType) {
+        let area_position = *area.position();
+        let width = area.width() as i32;
+        let positions = (0..area.height() as i32)
+            .flat_map(move |y| (0..width).map(move |x| area_position + Position::new(x, y)));
+        self.fill_positions(area, positions, tile_type);
+    }
+
+    /// Sets every local `Position` on the outermost ring of `area` to `tile_type`; the `SparseMap` will expand to fit `area`, as per `tile_type_at_local_set`. A zero-sized `area` does nothing.
+    ///
+    /// See [`fill_area`](#method.fill_area) for an example.
+    pub fn fill_border(&mut self, area: Area, tile_type: TileType) {
+        let left = area.position().x();
+        let top = area.position().y();
+        let right = left + area.width() as i32 - 1;
+        let bottom = top + area.height() as i32 - 1;
+        let rows = (left..=right).flat_map(move |x| {
+            let bottom_row = if bottom > top { Some(bottom) } else { None };
+            std::iter::once(top)
+                .chain(bottom_row)
+                .map(move |y| Position::new(x, y))
+        });
+        let columns = (top + 1..bottom).flat_map(move |y| {
+            let right_column = if right > left { Some(right) } else { None };
+            std::iter::once(left)
+                .chain(right_column)
+                .map(move |x| Position::new(x, y))
+        });
+        self.fill_positions(area, rows.chain(columns), tile_type);
+    }
+
+    fn fill_positions(
+        &mut self,
+        area: Area,
+        positions: impl Iterator<Item = Position>,
+        tile_type: TileType,
+    ) {
+        if area.width() == 0 || area.height() == 0 {
+            return;
+        }
+
+        if !self.sub_maps.is_empty() || self.tile_observer.is_some() {
+            // Sub-maps are written through, and the observer is called, as per `tile_type_at_local_set`.
+            for position in positions {
+                self.tile_type_at_local_set(position, tile_type);
+            }
+            return;
+        }
+
+        let self_position = *self.position();
+        for position in positions.filter(|position| position.x() >= 0 && position.y() >= 0) {
+            self.tiles.insert(position + self_position, tile_type);
+        }
+
+        let right = area.position().x() + area.width() as i32;
+        let bottom = area.position().y() + area.height() as i32;
+        *self.size_mut().width_mut() = self.size().width().max(right.max(0) as u32);
+        *self.size_mut().height_mut() = self.size().height().max(bottom.max(0) as u32);
+    }
+
+    /// Sets every local `Position` within `area` to `TileType::Wall` with a probability of `p_wall`, and otherwise to `TileType::Floor`, drawing from a small deterministic pseudo-random number generator seeded with `seed`.
+    ///
+    /// The same `area`, `seed`, and `p_wall` always produce the same layout, on every platform; tiles are drawn in order of row, and then column. `p_wall` is clamped to the range `[0, 1]`, with `NaN` treated as `0`. The `SparseMap` will expand to fit `area`, as per [`fill_area`](#method.fill_area). The generator is not suitable for cryptography.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let area = Area::new(Position::zero(), Size::new(16, 16));
+    /// let fill = |seed, p_wall| {
+    ///     let mut sparse_map = SparseMap::default();
+    ///     sparse_map.fill_random(area, seed, p_wall);
+    ///     sparse_map.to_ascii()
+    /// };
+    ///
+    /// assert!(fill(7, 0.45) == fill(7, 0.45));
+    /// assert!(fill(7, 0.45) != fill(8, 0.45));
+    /// assert!(fill(0, 0.45) != fill(1, 0.45));
+    ///
+    /// let walls = fill(7, 0.45).chars().filter(|c| *c == '#').count();
+    /// assert!(walls > 64 && walls < 192);
+    /// assert!(!fill(7, 0.0).contains('#'));
+    /// assert!(!fill(7, 1.0).contains('.'));
+    /// assert!(fill(7, 2.0) == fill(7, 1.0));
+    /// assert!(fill(7, -1.0) == fill(7, f64::NAN));
+    /// ```
+    pub fn fill_random(&mut self, area: Area, seed: u64, p_wall: f64) {
+        let p_wall = p_wall.clamp(0.0, 1.0);
+        let mut rng = XorShift::new(seed);
+        for y in area.position().y()..area.position().y() + area.height() as i32 {
+            for x in area.position().x()..area.position().x() + area.width() as i32 {
+                let tile_type = if rng.next_f64() < p_wall {
+                    TileType::Wall
+                } else {
+                    TileType::Floor
+                };
+                self.tile_type_at_local_set(Position::new(x, y), tile_type);
+            }
+        }
+    }
+
+    /// Bakes the resolved tiles of every sub-map directly into this `SparseMap`, then removes the sub-maps; afterwards, `tile_type_at_local` returns the same results without reading from `MAPS`.
+    ///
+    /// Tiles are resolved as per `tile_type_at_local`, using `TileTypeStandardCmp`, so nested sub-maps are flattened as well. Only tiles are baked in; the `Portal`s of sub-maps are not copied, and the sub-maps themselves remain registered, and unchanged.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let nested = SparseMap::new();
+    /// let sub_map = SparseMap::new();
+    /// {
+    ///     let maps = MAPS.read();
+    ///     let mut nested_map = maps[nested].write();
+    ///     nested_map.tile_type_at_local_set(Position::new(0, 0), TileType::Portal);
+    ///     nested_map.tile_type_at_local_set(Position::new(1, 0), TileType::Floor);
+    ///     drop(nested_map);
+    ///
+    ///     let mut sub_map_map = maps[sub_map].write();
+    ///     for x in 0..3 {
+    ///         sub_map_map.tile_type_at_local_set(Position::new(x, 0), TileType::Wall);
+    ///     }
+    ///     sub_map_map.tile_type_at_local_set(Position::new(0, 1), TileType::Void);
+    ///     sub_map_map.add_sub_map(Position::new(1, 0), nested);
+    /// }
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Void);
+    /// sparse_map.add_sub_map(Position::new(1, 1), sub_map);
+    ///
+    /// let positions: Vec<Position> = (-1..5)
+    ///     .flat_map(|y| (-1..5).map(move |x| Position::new(x, y)))
+    ///     .collect();
+    /// let before: Vec<Option<TileType>> = positions
+    ///     .iter()
+    ///     .map(|position| sparse_map.tile_type_at_local(*position))
+    ///     .collect();
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 1)) == Some(TileType::Portal));
+    ///
+    /// sparse_map.flatten_sub_maps();
+    /// assert!(sparse_map.sub_map_count() == 0);
+    ///
+    /// // The sub-maps are no longer read from.
+    /// unregister_map(sub_map);
+    /// unregister_map(nested);
+    /// let after: Vec<Option<TileType>> = positions
+    ///     .iter()
+    ///     .map(|position| sparse_map.tile_type_at_local(*position))
+    ///     .collect();
+    /// assert!(before == after);
+    /// ```
+    pub fn flatten_sub_maps(&mut self) {
+        if self.sub_maps.is_empty() {
+            return;
+        }
+
+        let self_position = *self.position();
+        let mut positions = HashSet::new();
+        for sub_map in self.sub_maps.iter() {
+            let sub_map_size = *MAPS.read()[sub_map.value()].read().size();
+            let offset = self.sub_map_offset(sub_map);
+            for y in 0..sub_map_size.height() as i32 {
+                for x in 0..sub_map_size.width() as i32 {
+                    positions.insert(offset + Position::new(x, y));
+                }
+            }
+        }
+
+        let resolved: Vec<(Position, Option<TileType>)> = positions
+            .into_iter()
+            .map(|position| (position, self.tile_type_at_local(position)))
+            .collect();
+
+        self.sub_maps.clear();
+        for (position, tile_type) in resolved {
+            if let Some(tile_type) = tile_type {
+                self.tiles.insert(position + self_position, tile_type);
+            }
+        }
+    }
+
+    fn flip(
+        &mut self,
+        flip_position: &dyn Fn(Position) -> Position,
+        adjust_position: Position,
+        flip_facing: &dyn Fn(CardinalDirection) -> CardinalDirection,
+        flip_sub_map: &dyn Fn(&mut SparseMap),
+        sub_map_adjustment: &dyn Fn(Size) -> Position,
+    ) {
+        let self_position = *self.position();
+
+        // As with `rotate_sub_maps`, each sub-map is mirrored once, and moved so that its mirrored tiles land where this map's mirrored tiles expect them.
+        let mut sub_map_adjustments = HashMap::<MapId, Position>::new();
+        for sub_map in self.sub_maps.iter_mut() {
+            let adjustment = match sub_map_adjustments.get(&sub_map.value()) {
+                Some(adjustment) => *adjustment,
+                None => {
+                    let (size, copy) = {
+                        let maps = MAPS.read();
+                        let map = match maps.get(sub_map.value()) {
+                            Some(map) => map.read(),
+                            None => continue,
+                        };
+                        (*map.size(), sparse_map_copy(&**map))
+                    };
+                    if let Some(mut copy) = copy {
+                        flip_sub_map(&mut copy);
+                        *MAPS.read()[sub_map.value()].write() = Box::new(copy);
+                    }
+
+                    let adjustment = sub_map_adjustment(size);
+                    sub_map_adjustments.insert(sub_map.value(), adjustment);
+                    adjustment
+                }
+            };
+
+            let offset = *sub_map.local_position() - self_position;
+            *sub_map.local_position_mut() =
+                self_position + adjust_position + flip_position(offset) - adjustment;
+        }
+
+        for portal_mut in self.portals.iter_mut() {
+            *portal_mut.local_position_mut() =
+                adjust_position + flip_position(*portal_mut.local_position());
+            *portal_mut.portal_to_map_facing_mut() =
+                flip_facing(*portal_mut.portal_to_map_facing());
+        }
+
+        self.tiles = self
+            .tiles
+            .drain()
+            .map(|(tile_position, tile_type)| {
+                let tile_local_position = tile_position - self_position;
+                let new_tile_local_position = adjust_position + flip_position(tile_local_position);
+                (self_position + new_tile_local_position, tile_type)
+            })
+            .collect();
+        self.remap_layers(&|tile_position| {
+            Some(self_position + adjust_position + flip_position(tile_position - self_position))
+        });
+    }
+
+    /// Mirrors the `SparseMap` from east to west, around its center; its `Position` and `Size` are unchanged.
+    ///
+    /// The local `Position` of every tile and `Portal` is mirrored, and the `Portal`'s facing is reflected from `CardinalDirection::East` to `CardinalDirection::West`, and vice versa. Sub-maps are mirrored too, as with `rotate`: each is moved to its mirrored place, and the contents of each `SparseMap` sub-map are mirrored in turn, in `MAPS`, so that the tiles they provide mirror with the rest. The contents of sub-maps which are not `SparseMap`s are moved, but not mirrored. This takes a write lock on each sub-map.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// let l_shape = [
+    ///     Position::new(0, 0),
+    ///     Position::new(0, 1),
+    ///     Position::new(0, 2),
+    ///     Position::new(1, 2),
+    /// ];
+    /// for position in l_shape.iter() {
+    ///     sparse_map.tile_type_at_local_set(*position, TileType::Wall);
+    /// }
+    /// sparse_map.add_portal(
+    ///     Position::new(2, 0),
+    ///     CardinalDirection::East,
+    ///     Position::zero(),
+    ///     target,
+    /// );
+    /// let original = sparse_map.clone();
+    ///
+    /// sparse_map.flip_horizontal();
+    /// assert!(*sparse_map.size() == Size::new(3, 3));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 2)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 2)) == None);
+    /// let portal = sparse_map.get_portal_at(0).unwrap();
+    /// assert!(*portal.local_position() == Position::new(0, 0));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::West);
+    ///
+    /// sparse_map.flip_horizontal();
+    /// for y in 0..3 {
+    ///     for x in 0..3 {
+    ///         let position = Position::new(x, y);
+    ///         assert!(sparse_map.tile_type_at_local(position) == original.tile_type_at_local(position));
+    ///     }
+    /// }
+    /// let portal = sparse_map.get_portal_at(0).unwrap();
+    /// assert!(*portal.local_position() == Position::new(2, 0));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::East);
+    ///
+    /// // The tiles provided by sub-maps are mirrored with the rest.
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 1), TileType::Wall);
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::zero(), TileType::Portal);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 0), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    /// assert!(sparse_map.to_ascii() == "+#.");
+    ///
+    /// sparse_map.flip_horizontal();
+    /// assert!(sparse_map.to_ascii() == ".#+");
+    /// assert!(MAPS.read()[sub_map].read().tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    /// sparse_map.flip_horizontal();
+    /// assert!(sparse_map.to_ascii() == "+#.");
+    /// ```
+    pub fn flip_horizontal(&mut self) {
+        let adjust_position = Position::new((self.area().width() as i32 - 1).max(0), 0);
+        self.flip(
+            &|position| Position::new(-position.x(), position.y()),
+            adjust_position,
+            &|facing| match facing {
+                CardinalDirection::East => CardinalDirection::West,
+                CardinalDirection::West => CardinalDirection::East,
+                _ => facing,
+            },
+            &|sub_map| sub_map.flip_horizontal(),
+            &|size| Position::new((size.width() as i32 - 1).max(0), 0),
+        );
+    }
+
+    /// Mirrors the `SparseMap` from north to south, around its center; its `Position` and `Size` are unchanged.
+    ///
+    /// The local `Position` of every tile and `Portal` is mirrored, and the `Portal`'s facing is reflected from `CardinalDirection::North` to `CardinalDirection::South`, and vice versa. Sub-maps are mirrored as per [`flip_horizontal`](#method.flip_horizontal). This takes a write lock on each sub-map.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// let l_shape = [
+    ///     Position::new(0, 0),
+    ///     Position::new(0, 1),
+    ///     Position::new(0, 2),
+    ///     Position::new(1, 2),
+    /// ];
+    /// for position in l_shape.iter() {
+    ///     sparse_map.tile_type_at_local_set(*position, TileType::Wall);
+    /// }
+    /// sparse_map.add_portal(
+    ///     Position::new(1, 0),
+    ///     CardinalDirection::North,
+    ///     Position::zero(),
+    ///     target,
+    /// );
+    /// let original = sparse_map.clone();
+    ///
+    /// sparse_map.flip_vertical();
+    /// assert!(*sparse_map.size() == Size::new(2, 3));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 2)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == None);
+    /// let portal = sparse_map.get_portal_at(0).unwrap();
+    /// assert!(*portal.local_position() == Position::new(1, 2));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::South);
+    ///
+    /// sparse_map.flip_vertical();
+    /// for y in 0..3 {
+    ///     for x in 0..2 {
+    ///         let position = Position::new(x, y);
+    ///         assert!(sparse_map.tile_type_at_local(position) == original.tile_type_at_local(position));
+    ///     }
+    /// }
+    /// let portal = sparse_map.get_portal_at(0).unwrap();
+    /// assert!(*portal.local_position() == Position::new(1, 0));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::North);
+    ///
+    /// // The tiles provided by sub-maps, and by their own sub-maps, are mirrored with the rest.
+    /// let nested = SparseMap::new_filled(Size::new(1, 1), TileType::Portal);
+    /// let sub_map = SparseMap::new_filled(Size::new(1, 2), TileType::Wall);
+    /// try_add_sub_map(sub_map, Position::zero(), nested).unwrap();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 2), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    /// assert!(sparse_map.to_ascii() == "+\n#\n.");
+    ///
+    /// sparse_map.flip_vertical();
+    /// assert!(sparse_map.to_ascii() == ".\n#\n+");
+    /// ```
+    pub fn flip_vertical(&mut self) {
+        let adjust_position = Position::new(0, (self.area().height() as i32 - 1).max(0));
+        self.flip(
+            &|position| Position::new(position.x(), -position.y()),
+            adjust_position,
+            &|facing| match facing {
+                CardinalDirection::North => CardinalDirection::South,
+                CardinalDirection::South => CardinalDirection::North,
+                _ => facing,
+            },
+            &|sub_map| sub_map.flip_vertical(),
+            &|size| Position::new(0, (size.height() as i32 - 1).max(0)),
+        );
+    }
+
+    /// Calls `f` with the local `Position` of, and a mutable reference to, every tile stored directly in this `SparseMap` within the local `area`, so that it can be changed in place; local `Position`s within `area` with no stored tile are not visited.
+    ///
+    /// The tiles are visited in no particular order, each once, without looking up each `Position` in turn. As the tiles are changed in place, the changes are not written through to sub-maps, nor passed to an observer set with [`set_tile_observer`](#method.set_tile_observer). Use [`for_each_tile_mut_or_insert`](#method.for_each_tile_mut_or_insert) to visit every `Position` within `area`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(5, 5)), TileType::Floor);
+    /// sparse_map.tile_type_at_local_remove(Position::new(2, 2));
+    ///
+    /// let mut visited = Vec::new();
+    /// sparse_map.for_each_tile_mut(Area::new(Position::new(1, 1), Size::new(3, 2)), &mut |position, tile_type| {
+    ///     visited.push(position);
+    ///     *tile_type = TileType::Wall;
+    /// });
+    /// assert!(visited.len() == 5);
+    /// assert!(!visited.contains(&Position::new(2, 2)));
+    /// assert!(sparse_map.count_tile_type(TileType::Wall) == 5);
+    /// assert!(sparse_map.to_ascii() == ".....\n.###.\n.# #.\n.....\n.....");
+    /// ```
+    pub fn for_each_tile_mut(&mut self, area: Area, f: &mut dyn FnMut(Position, &mut TileType)) {
+        let self_position = *self.position();
+        let left = area.position().x();
+        let top = area.position().y();
+        let right = left + area.width() as i32;
+        let bottom = top + area.height() as i32;
+        for (key, tile_type) in self.tiles.iter_mut() {
+            let position = *key - self_position;
+            if position.x() >= left
+                && position.y() >= top
+                && position.x() < right
+                && position.y() < bottom
+            {
+                f(position, tile_type);
+            }
+        }
+    }
+
+    /// Calls `f` with the local `Position` of, and a mutable reference to, the tile stored directly in this `SparseMap` at every local `Position` within `area`, in order of row, and then column, first setting `default` through `tile_type_at_local_set` wherever no tile is stored.
+    ///
+    /// As per `tile_type_at_local_set`, the `SparseMap` expands to fit the inserted tiles, and local `Position`s with a negative coordinate are skipped; the changes made by `f` are made in place, as per [`for_each_tile_mut`](#method.for_each_tile_mut).
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 0), TileType::Wall);
+    ///
+    /// let mut walls = 0;
+    /// sparse_map.for_each_tile_mut_or_insert(Area::new(Position::new(-1, 0), Size::new(4, 2)), TileType::Floor, &mut |_, tile_type| {
+    ///     if *tile_type == TileType::Wall {
+    ///         walls += 1;
+    ///         *tile_type = TileType::Portal;
+    ///     }
+    /// });
+    /// assert!(walls == 1);
+    /// assert!(*sparse_map.size() == Size::new(3, 2));
+    /// assert!(sparse_map.to_ascii() == ".+.\n...");
+    /// ```
+    pub fn for_each_tile_mut_or_insert(
+        &mut self,
+        area: Area,
+        default: TileType,
+        f: &mut dyn FnMut(Position, &mut TileType),
+    ) {
+        for y in area.position().y()..area.position().y() + area.height() as i32 {
+            for x in area.position().x()..area.position().x() + area.width() as i32 {
+                let position = Position::new(x, y);
+                if x < 0 || y < 0 {
+                    continue;
+                }
+
+                let key = position + *self.position();
+                if !self.tiles.contains_key(&key) {
+                    self.tile_type_at_local_set(position, default);
+                }
+                if let Some(tile_type) = self.tiles.get_mut(&key) {
+                    f(position, tile_type);
+                }
+            }
+        }
+    }
+
+    /// Creates and registers a new `SparseMap` from ASCII text, and returns its `MapId`.
+    ///
+    /// Each line of `s` is a row, with the row index as the local y coordinate, and the column index within the line as the local x coordinate. Every character for which `f` returns `Some(tile_type)` is set with `tile_type_at_local_set`; characters for which `f` returns `None` are skipped.
+    ///
+    /// The `SparseMap`'s `Size` is the width of the widest line by the number of lines; lines shorter than the widest leave their missing trailing columns without a tile, as with characters for which `f` returns `None`, so `tile_type_at_local` returns `None` for them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let ascii = "#####\n#..\n#.#.#\n#####";
+    /// let map_id = SparseMap::from_ascii(ascii, &|c| match c {
+    ///     '.' => Some(TileType::Floor),
+    ///     '#' => Some(TileType::Wall),
+    ///     '+' => Some(TileType::Portal),
+    ///     _ => None,
+    /// });
+    ///
+    /// let maps = MAPS.read();
+    /// let map = maps[map_id].read();
+    /// assert!(*map.size() == Size::new(5, 4));
+    /// assert!(map.tile_type_at_local(Position::new(2, 1)) == Some(TileType::Floor));
+    /// assert!(map.tile_type_at_local(Position::new(4, 1)) == None);
+    /// assert!(map.to_ascii() == "#####\n#..  \n#.#.#\n#####");
+    /// ```
+    pub fn from_ascii(s: &str, f: &dyn Fn(char) -> Option<TileType>) -> MapId {
+        let mut sparse_map = Self::default();
+        let mut width = 0;
+        let mut height = 0;
+        for (y, line) in s.lines().enumerate() {
+            let mut line_width = 0;
+            for (x, c) in line.chars().enumerate() {
+                if let Some(tile_type) = f(c) {
+                    sparse_map.tile_type_at_local_set(Position::new(x as i32, y as i32), tile_type);
+                }
+                line_width = x as u32 + 1;
+            }
+
+            width = width.max(line_width);
+            height = y as u32 + 1;
+        }
+
+        *sparse_map.size_mut() = Size::new(width, height);
+        register_map(sparse_map)
+    }
+
+    /// Creates and registers a new `SparseMap` of the given `size` from a grid of tiles in order of row, and then column, as returned by [`to_grid`](#method.to_grid), and returns its `MapId`.
+    ///
+    /// The tile at index `y * width + x` is set at the local `Position` `(x, y)` with `tile_type_at_local_set`, unless it is `skip`, in which case no tile is stored there; the `SparseMap`'s size is `size`, even if its last rows or columns are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `tiles` is not `size.width() * size.height()`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let size = Size::new(3, 2);
+    /// let grid = [
+    ///     TileType::Wall,
+    ///     TileType::Void,
+    ///     TileType::Wall,
+    ///     TileType::Floor,
+    ///     TileType::Floor,
+    ///     TileType::Portal,
+    /// ];
+    /// let map_id = SparseMap::from_grid(size, &grid, TileType::Void);
+    /// {
+    ///     let maps = MAPS.read();
+    ///     let map = maps[map_id].read();
+    ///     assert!(*map.size() == size);
+    ///     assert!(map.tile_type_at_local(Position::new(1, 0)) == None);
+    ///     assert!(map.to_ascii() == "# #\n..+");
+    /// }
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(map_id, Position::zero(), false);
+    /// assert!(sparse_map.tiles_iter().count() == 5);
+    /// assert!(sparse_map.to_grid(TileType::Void) == (size, grid.to_vec()));
+    /// ```
+    pub fn from_grid(size: Size, tiles: &[TileType], skip: TileType) -> MapId {
+        let width = size.width() as usize;
+        assert!(
+            tiles.len() == width * size.height() as usize,
+            "a grid of size {} holds {} tiles, not {}",
+            size,
+            width * size.height() as usize,
+            tiles.len()
+        );
+
+        let mut sparse_map = Self::default();
+        for (index, tile_type) in tiles.iter().enumerate() {
+            if *tile_type != skip {
+                let position = Position::new((index % width) as i32, (index / width) as i32);
+                sparse_map.tile_type_at_local_set(position, *tile_type);
+            }
+        }
+
+        *sparse_map.size_mut() = size;
+        register_map(sparse_map)
+    }
+
+    pub(crate) fn from_parts(
+        map_id: MapId,
+        area: Area,
+        tiles: HashMap<Position, TileType>,
+        portals: Vec<(Portal, PortalKind)>,
+        sub_maps: Vec<SubMap>,
+    ) -> Self {
+        let (portals, portal_kinds) = portals.into_iter().unzip();
+        Self {
+            map_id,
+            area,
+            tiles,
+            layers: BTreeMap::new(),
+            doors: HashMap::new(),
+            tile_data: HashMap::new(),
+            portal_kinds,
+            portals,
+            sub_maps,
+            out_of_bounds_tile: None,
+            tile_priority: None,
+            tile_observer: None,
+        }
+    }
+
+    /// Creates and registers a `SparseMap` from the run-length encoding produced by [`to_rle`](#method.to_rle), with a fresh `MapId`, and returns its `MapId`.
+    ///
+    /// Whitespace between the fields, and the runs, is ignored, so the runs may be wrapped freely. As the targets of `Portal`s and sub-maps are raw `MapId`s, they are only meaningful while the same maps are registered.
+    ///
+    /// Returns an [`RleError`](enum.RleError.html), registering nothing, if `s` is not in the format produced by `to_rle`, such as when a header line is malformed, the width or height is negative, a character is unknown, or the runs do not cover the area exactly.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::*;
+    /// assert!(SparseMap::from_rle("RLE 0 0 2 1\n1.1#").is_ok());
+    /// assert!(SparseMap::from_rle("RLE 0 0 -1 2\n") == Err(RleError::InvalidSize(-1, 2)));
+    /// assert!(SparseMap::from_rle("RLE 0 0 2 1\n3.") == Err(RleError::RunLength(None, 2)));
+    /// assert!(SparseMap::from_rle("RLE 0 0 2 1\n1.") == Err(RleError::RunLength(Some(1), 2)));
+    /// assert!(matches!(SparseMap::from_rle("RLE 0 0 2 1\n2?"), Err(RleError::Format(_))));
+    /// assert!(matches!(SparseMap::from_rle(""), Err(RleError::Format(_))));
+    /// ```
+    ///
+    /// See [`to_rle`](#method.to_rle) for a round trip.
+    pub fn from_rle(s: &str) -> Result<MapId, RleError> {
+        let format_error = |message: &str| RleError::Format(message.to_string());
+        let parse_i32 = |field: Option<&str>| -> Result<i32, RleError> {
+            field
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(|| format_error("a field is not an integer"))
+        };
+        let parse_map_id = |field: Option<&str>| -> Result<MapId, RleError> {
+            field
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(|| format_error("a target is not a MapId"))
+        };
+
+        let mut lines = s.lines();
+        let mut header = lines.next().unwrap_or_default().split_whitespace();
+        if header.next() != Some("RLE") {
+            return Err(format_error("the header is missing"));
+        }
+        let position = Position::new(parse_i32(header.next())?, parse_i32(header.next())?);
+        let (width, height) = (parse_i32(header.next())?, parse_i32(header.next())?);
+        let tile_count = if width < 0 || height < 0 {
+            None
+        } else {
+            (width as usize).checked_mul(height as usize)
+        }
+        .ok_or(RleError::InvalidSize(width as i64, height as i64))?;
+        let size = Size::new(width as u32, height as u32);
+        let width = width as usize;
+
+        let mut portals = Vec::new();
+        let mut sub_maps = Vec::new();
+        let mut runs = String::new();
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("P") => {
+                    let local_position =
+                        Position::new(parse_i32(fields.next())?, parse_i32(fields.next())?);
+                    let facing = match fields.next() {
+                        Some("N") => CardinalDirection::North,
+                        Some("E") => CardinalDirection::East,
+                        Some("S") => CardinalDirection::South,
+                        Some("W") => CardinalDirection::West,
+                        _ => {
+                            return Err(format_error("a portal facing is not one of N, E, S, or W"))
+                        }
+                    };
+                    let portal_to_map_position =
+                        Position::new(parse_i32(fields.next())?, parse_i32(fields.next())?);
+                    let target = parse_map_id(fields.next())?;
+                    let portal_kind = match fields.next() {
+                        None => PortalKind::Open,
+                        Some("locked") => PortalKind::Locked,
+                        Some("one_way") => PortalKind::OneWay,
+                        Some("stairs") => PortalKind::Stairs,
+                        Some(field) => field
+                            .strip_prefix("tagged:")
+                            .and_then(|tag| tag.parse().ok())
+                            .map(PortalKind::Tagged)
+                            .ok_or_else(|| format_error("a portal kind is not recognised"))?,
+                    };
+                    portals.push((
+                        Portal::new(local_position, facing, portal_to_map_position, target),
+                        portal_kind,
+                    ));
+                }
+                Some("S") => {
+                    let local_position =
+                        Position::new(parse_i32(fields.next())?, parse_i32(fields.next())?);
+                    let target = parse_map_id(fields.next())?;
+                    sub_maps.push(SubMap::new(local_position, target));
+                }
+                _ => runs.extend(line.chars().filter(|c| !c.is_whitespace())),
+            }
+        }
+
+        let mut tiles = HashMap::new();
+        let mut index: usize = 0;
+        let mut count = String::new();
+        for c in runs.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+
+            let tile_type = match c {
+                '-' => None,
+                '_' => Some(TileType::Void),
+                '#' => Some(TileType::Wall),
+                '.' => Some(TileType::Floor),
+                '+' => Some(TileType::Portal),
+                _ => return Err(RleError::Format(format!("{:?} is not a tile character", c))),
+            };
+            let run: usize = count
+                .parse()
+                .map_err(|_| format_error("a run has no count"))?;
+            count.clear();
+            let end = index
+                .checked_add(run)
+                .filter(|end| *end <= tile_count)
+                .ok_or(RleError::RunLength(None, tile_count))?;
+            if let Some(tile_type) = tile_type {
+                for index in index..end {
+                    let local_position =
+                        Position::new((index % width) as i32, (index / width) as i32);
+                    tiles.insert(local_position + position, tile_type);
+                }
+            }
+            index = end;
+        }
+        if !count.is_empty() {
+            return Err(format_error("the last run has no tile character"));
+        }
+        if index != tile_count {
+            return Err(RleError::RunLength(Some(index), tile_count));
+        }
+
+        Ok(register_map(Self::from_parts(
+            get_new_map_id(),
+            Area::new(position, size),
+            tiles,
+            portals,
+            sub_maps,
+        )))
+    }
+
+    /// Returns whether this `SparseMap` stores no tiles, in any layer, and has no `Portal`s, and no sub-maps, regardless of its area.
+    ///
+    /// Unlike an empty area, this does not depend upon how the area was grown: a `SparseMap` whose size was set through `size_mut`, but which stores nothing, is empty, while one which stores a `TileType::Void` tile is not.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// assert!(sparse_map.is_empty());
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(-1, 0), TileType::Floor);
+    /// *sparse_map.size_mut() = Size::new(4, 4);
+    /// assert!(sparse_map.is_empty());
+    ///
+    /// let mut single_tile = SparseMap::default();
+    /// single_tile.tile_type_at_local_set(Position::zero(), TileType::Void);
+    /// assert!(!single_tile.is_empty());
+    /// assert!(*single_tile.size() == Size::new(1, 1));
+    ///
+    /// single_tile.tile_type_at_local_remove(Position::zero());
+    /// assert!(single_tile.is_empty());
+    /// single_tile.set_layer_tile(1, Position::zero(), TileType::Floor);
+    /// assert!(!single_tile.is_empty());
+    ///
+    /// single_tile.clear();
+    /// single_tile.add_sub_map(Position::zero(), SparseMap::new());
+    /// assert!(!single_tile.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+            && self.layers.values().all(|tiles| tiles.is_empty())
+            && self.portals.is_empty()
+            && self.sub_maps.is_empty()
+    }
+
+    /// Returns whether the local `Position` lies within this `SparseMap`'s area, without consulting its tiles, or those of its sub-maps.
+    ///
+    /// This differs from `intersects_local_position`, which also requires the tile there to resolve to something other than `TileType::Void`, or no tile; as such, this is cheaper, and takes no locks.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(3, 3));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.tile_type_at_local_remove(Position::new(1, 1));
+    ///
+    /// assert!(sparse_map.is_local_position_in_area(Position::new(1, 1)));
+    /// assert!(!sparse_map.intersects_local_position(Position::new(1, 1)));
+    /// assert!(sparse_map.is_local_position_in_area(Position::new(2, 2)));
+    /// assert!(sparse_map.intersects_local_position(Position::new(2, 2)));
+    /// assert!(!sparse_map.is_local_position_in_area(Position::new(3, 0)));
+    /// assert!(!sparse_map.is_local_position_in_area(Position::new(0, -1)));
+    /// ```
+    pub fn is_local_position_in_area(&self, pos: Position) -> bool {
+        self.area().intersects_local_position(pos)
+    }
+
+    /// Returns an iterator over every local `Position` within both `area` and this `SparseMap`'s area, and its tile, as resolved through `tile_type_at_local`, in order of row, and then column.
+    ///
+    /// If the two areas do not intersect, nothing is yielded.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(5, 5)), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 2), TileType::Void);
+    ///
+    /// let window: Vec<(Position, Option<TileType>)> = sparse_map
+    ///     .iter_area(Area::new(Position::new(1, 1), Size::new(2, 2)))
+    ///     .collect();
+    /// assert!(
+    ///     window
+    ///         == vec![
+    ///             (Position::new(1, 1), Some(TileType::Floor)),
+    ///             (Position::new(2, 1), Some(TileType::Floor)),
+    ///             (Position::new(1, 2), None),
+    ///             (Position::new(2, 2), Some(TileType::Wall)),
+    ///         ]
+    /// );
+    ///
+    /// let clipped = sparse_map.iter_area(Area::new(Position::new(4, -1), Size::new(3, 2)));
+    /// assert!(clipped.map(|(position, _)| position).collect::<Vec<_>>() == vec![Position::new(4, 0)]);
+    /// assert!(sparse_map.iter_area(Area::new(Position::new(5, 0), Size::new(2, 2))).count() == 0);
+    /// ```
+    pub fn iter_area(&self, area: Area) -> impl Iterator<Item = (Position, Option<TileType>)> + '_ {
+        let left = area.position().x().max(0);
+        let top = area.position().y().max(0);
+        let right = (area.position().x() + area.width() as i32).min(self.width() as i32);
+        let bottom = (area.position().y() + area.height() as i32).min(self.height() as i32);
+        (top..bottom)
+            .flat_map(move |y| (left..right).map(move |x| Position::new(x, y)))
+            .map(move |position| (position, self.tile_type_at_local(position)))
+    }
+
+    /// Returns the tile stored at the given local `Position` of `layer`; layer `0` is resolved through `tile_type_at_local`, while other layers hold only what was set with [`set_layer_tile`](#method.set_layer_tile).
+    ///
+    /// See [`set_layer_tile`](#method.set_layer_tile) for an example.
+    pub fn layer_tile(&self, layer: u8, pos: Position) -> Option<TileType> {
+        if layer == 0 {
+            return self.tile_type_at_local(pos);
+        }
+
+        self.layers
+            .get(&layer)?
+            .get(&(pos + *self.position()))
+            .copied()
+    }
+
+    /// Moves the sub-map at `index` to `new_local_position`, as given to `add_sub_map`, and recomputes the area, as per [`recompute_area_from_sub_maps`](#method.recompute_area_from_sub_maps); if there is no such sub-map, nothing is changed.
+    ///
+    /// The sub-map's tiles are resolved through `tile_type_at_local` at their new `Position`s, and no longer at their old ones.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 2), TileType::Floor);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 3), TileType::Wall);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    /// assert!(*sparse_map.size() == Size::new(2, 4));
+    ///
+    /// sparse_map.move_sub_map(0, Position::new(3, 0));
+    /// assert!(*sparse_map.get_sub_map_at(0).unwrap().local_position() == Position::new(3, 0));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 0)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(4, 1)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 0)) == None);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 3)) == Some(TileType::Wall));
+    /// assert!(*sparse_map.size() == Size::new(5, 4));
+    /// ```
+    pub fn move_sub_map(&mut self, index: usize, new_local_position: Position) {
+        let sub_map = match self.sub_maps.get_mut(index) {
+            Some(sub_map) => sub_map,
+            None => return,
+        };
+
+        *sub_map.local_position_mut() = new_local_position;
+        self.recompute_area_from_sub_maps();
+    }
+
+    /// Returns the local `Position` nearest to `from` whose tile, as resolved through `tile_type_at_local`, `matches`, or `None` if there is no such tile within this `SparseMap`'s area.
+    ///
+    /// Distance is measured as the number of 8-connected steps, so the search expands outwards in square rings around `from`; within a ring, ties are broken in order of row, and then column, so the top-most, and then left-most, match wins. `from` itself is checked first, and may lie outside of the area.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(9, 7)), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(6, 2), TileType::Floor);
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    ///
+    /// for from in [Position::new(0, 0), Position::new(4, 3), Position::new(8, 6), Position::new(6, 2)] {
+    ///     assert!(sparse_map.nearest_tile(from, &is_floor) == Some(Position::new(6, 2)));
+    /// }
+    /// assert!(sparse_map.nearest_tile(Position::new(20, -5), &is_floor) == Some(Position::new(6, 2)));
+    ///
+    /// // Both floors are 2 steps from (4, 4); the upper one wins.
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 6), TileType::Floor);
+    /// assert!(sparse_map.nearest_tile(Position::new(4, 4), &is_floor) == Some(Position::new(6, 2)));
+    /// assert!(sparse_map.nearest_tile(Position::new(3, 5), &is_floor) == Some(Position::new(2, 6)));
+    ///
+    /// assert!(sparse_map.nearest_tile(Position::new(4, 4), &|tile_type| tile_type == TileType::Portal) == None);
+    /// ```
+    pub fn nearest_tile(
+        &self,
+        from: Position,
+        matches: &dyn Fn(TileType) -> bool,
+    ) -> Option<Position> {
+        let width = self.width() as i32;
+        let height = self.height() as i32;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let max_radius = from
+            .x()
+            .abs()
+            .max((width - 1 - from.x()).abs())
+            .max(from.y().abs())
+            .max((height - 1 - from.y()).abs());
+        for radius in 0..=max_radius {
+            for y in (from.y() - radius).max(0)..=(from.y() + radius).min(height - 1) {
+                let on_edge = y == from.y() - radius || y == from.y() + radius;
+                for x in (from.x() - radius).max(0)..=(from.x() + radius).min(width - 1) {
+                    if !on_edge && x != from.x() - radius && x != from.x() + radius {
+                        continue;
+                    }
+
+                    let position = Position::new(x, y);
+                    if self.tile_type_at_local(position).is_some_and(matches) {
+                        return Some(position);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Creates a new `SparseMap`. As `SparseMap` expands to meet its use, no parameters need be supplied.
+    ///
+    /// `SparseMap::default()` creates the same map, with a fresh `MapId`, without registering it.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> MapId {
+        register_map(Self::default())
+    }
+
+    /// Creates and registers a new `SparseMap` of the given `Size`, with every local `Position` within it set to `tile_type`, and returns its `MapId`. A zero `Size` creates an empty map.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::new_filled(Size::new(5, 3), TileType::Floor);
+    /// let empty_map_id = SparseMap::new_filled(Size::zero(), TileType::Floor);
+    ///
+    /// let maps = MAPS.read();
+    /// let sparse_map = maps[map_id].read();
+    /// assert!(*sparse_map.size() == Size::new(5, 3));
+    /// assert!(sparse_map.to_ascii() == ".....\n.....\n.....");
+    ///
+    /// let empty_map = maps[empty_map_id].read();
+    /// assert!(*empty_map.size() == Size::zero());
+    /// assert!(empty_map.to_ascii() == "");
+    /// ```
+    pub fn new_filled(size: Size, tile_type: TileType) -> MapId {
+        let mut sparse_map = Self::default();
+        sparse_map.fill_area(Area::new(Position::zero(), size), tile_type);
+        register_map(sparse_map)
+    }
+
+    /// Creates and registers a new `SparseMap` of the given `Size`, with a ring of `TileType::Wall` around a `TileType::Floor` interior, and returns its `MapId`. A zero `Size` creates an empty map.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::new_room(Size::new(5, 4));
+    /// let maps = MAPS.read();
+    /// let sparse_map = maps[map_id].read();
+    /// assert!(*sparse_map.size() == Size::new(5, 4));
+    /// assert!(sparse_map.to_ascii() == "#####\n#...#\n#...#\n#####");
+    /// ```
+    pub fn new_room(size: Size) -> MapId {
+        let mut sparse_map = Self::default();
+        let area = Area::new(Position::zero(), size);
+        sparse_map.fill_area(area, TileType::Floor);
+        sparse_map.fill_border(area, TileType::Wall);
+        register_map(sparse_map)
+    }
+
+    /// Returns the smallest local `Area` which covers every occupied tile stored directly in this `SparseMap`, or `None` if there are none.
+    ///
+    /// Unlike [`shrink_to_fit`](#method.shrink_to_fit), this leaves the `SparseMap` unchanged, and considers only the stored tiles other than `TileType::Void`; `Portal`s, sub-maps, and layers do not widen the bounds.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// *sparse_map.size_mut() = Size::new(100, 100);
+    /// assert!(sparse_map.occupied_bounds() == None);
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(91, 95), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(97, 92), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(94, 98), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Void);
+    ///
+    /// assert!(sparse_map.occupied_bounds() == Some(Area::new(Position::new(91, 92), Size::new(7, 7))));
+    /// assert!(*sparse_map.size() == Size::new(100, 100));
+    /// ```
+    pub fn occupied_bounds(&self) -> Option<Area> {
+        let (min, max) = self
+            .occupied_iter()
+            .map(|(position, _)| (position, position))
+            .reduce(|(min, max), (low, high)| {
+                (
+                    Position::new(min.x().min(low.x()), min.y().min(low.y())),
+                    Position::new(max.x().max(high.x()), max.y().max(high.y())),
+                )
+            })?;
+
+        Some(Area::new(
+            min,
+            Size::new(
+                (max.x() - min.x() + 1) as u32,
+                (max.y() - min.y() + 1) as u32,
+            ),
+        ))
+    }
+
+    /// Returns an iterator over every occupied tile stored directly in this `SparseMap`, as its local `Position` and `TileType`; that is, every stored tile other than `TileType::Void`.
+    ///
+    /// Only the stored tiles are visited, so this takes time in proportion to their number, rather than to the size of the area; a map whose area spans millions of positions, but which holds a handful of tiles, is iterated as quickly as a small one. Tiles provided by sub-maps are not included. The order of iteration is unspecified; use [`occupied_iter_sorted`](#method.occupied_iter_sorted) for a deterministic order, or [`tiles_iter`](#method.tiles_iter) to include stored `Void` tiles.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// for i in 0..50 {
+    ///     sparse_map.tile_type_at_local_set(Position::new(i * 199, i * 203), TileType::Floor);
+    /// }
+    /// sparse_map.tile_type_at_local_set(Position::new(9999, 9999), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(5000, 0), TileType::Void);
+    /// assert!(*sparse_map.size() == Size::new(10000, 10000));
+    ///
+    /// assert!(sparse_map.occupied_iter().count() == 51);
+    /// assert!(sparse_map.tiles_iter().count() == 52);
+    /// assert!(sparse_map
+    ///     .occupied_iter()
+    ///     .all(|(position, _)| sparse_map.intersects_local_position(position)));
+    /// ```
+    pub fn occupied_iter(&self) -> impl Iterator<Item = (Position, TileType)> + '_ {
+        self.tiles_iter()
+            .filter(|(_, tile_type)| *tile_type != TileType::Void)
+    }
+
+    /// Returns an iterator over every occupied tile stored directly in this `SparseMap`, as per [`occupied_iter`](#method.occupied_iter), sorted by row, and then by column.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(5000, 20), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(7000, 10), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 20), TileType::Void);
+    /// sparse_map.tile_type_at_local_set(Position::new(10, 20), TileType::Floor);
+    ///
+    /// let tiles: Vec<(Position, TileType)> = sparse_map.occupied_iter_sorted().collect();
+    /// assert!(
+    ///     tiles
+    ///         == vec![
+    ///             (Position::new(7000, 10), TileType::Floor),
+    ///             (Position::new(10, 20), TileType::Floor),
+    ///             (Position::new(5000, 20), TileType::Wall),
+    ///         ]
+    /// );
+    /// ```
+    pub fn occupied_iter_sorted(&self) -> impl Iterator<Item = (Position, TileType)> {
+        self.tiles_iter_sorted()
+            .filter(|(_, tile_type)| *tile_type != TileType::Void)
+    }
+
+    /// Returns the `TileType` which `tile_type_at_local` returns for local `Position`s outside of this `SparseMap`'s area, if one has been set with [`set_out_of_bounds_tile`](#method.set_out_of_bounds_tile).
+    pub fn out_of_bounds_tile(&self) -> Option<TileType> {
+        self.out_of_bounds_tile
+    }
+
+    /// Returns the [`PortalKind`](enum.PortalKind.html) of the `Portal` at `index`, or `None` if there is no such `Portal`.
+    ///
+    /// See [`add_portal_with_kind`](#method.add_portal_with_kind) for an example.
+    pub fn portal_kind(&self, index: usize) -> Option<PortalKind> {
+        self.portal_kinds.get(index).copied()
+    }
+
+    /// Returns a mutable reference to the [`PortalKind`](enum.PortalKind.html) of the `Portal` at `index`, or `None` if there is no such `Portal`.
+    ///
+    /// See [`add_portal_with_kind`](#method.add_portal_with_kind) for an example.
+    pub fn portal_kind_mut(&mut self, index: usize) -> Option<&mut PortalKind> {
+        self.portal_kinds.get_mut(index)
+    }
+
+    /// Removes every `Portal` reported by [`validate_portals`](#method.validate_portals), as per [`remove_portal_at`](#method.remove_portal_at).
+    ///
+    /// See [`validate_portals`](#method.validate_portals) for an example.
+    pub fn prune_dangling_portals(&mut self) {
+        for index in self.validate_portals().into_iter().rev() {
+            self.remove_portal_at(index);
+        }
+    }
+
+    /// Returns a local `Position`, chosen uniformly at random, of a tile stored directly in this `SparseMap` for which `is_walkable` returns `true`, or `None` if there is no such tile.
+    ///
+    /// The tile is chosen by reservoir sampling, in a single pass over the stored tiles in order of row, and then column, drawing from a small deterministic pseudo-random number generator seeded with `seed`; the same tiles and `seed` always produce the same `Position`, on every platform, regardless of the order in which the tiles were set. The tiles of sub-maps are not considered. The generator is not suitable for cryptography.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(6, 5));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    ///
+    /// assert!(sparse_map.random_walkable(7, &is_floor) == Some(Position::new(1, 3)));
+    /// assert!(sparse_map.random_walkable(7, &is_floor) == sparse_map.clone().random_walkable(7, &is_floor));
+    /// for seed in 0..100 {
+    ///     let position = sparse_map.random_walkable(seed, &is_floor).unwrap();
+    ///     assert!(sparse_map.tile_type_at_local(position) == Some(TileType::Floor));
+    /// }
+    ///
+    /// let walls = SparseMap::default();
+    /// assert!(walls.random_walkable(7, &is_floor) == None);
+    /// sparse_map.replace_tiles(TileType::Floor, TileType::Wall);
+    /// assert!(sparse_map.random_walkable(7, &is_floor) == None);
+    /// ```
+    pub fn random_walkable(
+        &self,
+        seed: u64,
+        is_walkable: &dyn Fn(TileType) -> bool,
+    ) -> Option<Position> {
+        let mut rng = XorShift::new(seed);
+        let mut output = None;
+        let mut seen = 0;
+        for (position, tile_type) in self.tiles_iter_sorted() {
+            if !is_walkable(tile_type) {
+                continue;
+            }
+
+            // The `seen`th walkable tile replaces the choice with a probability of `1 / seen`.
+            seen += 1;
+            if rng.next_u64() % seen == 0 {
+                output = Some(position);
+            }
+        }
+
+        output
+    }
+
+    /// Clears `buf`, and fills it with the tile at every local `Position` within both `area` and this `SparseMap`'s area, as resolved through `tile_type_at_local`, in order of row, and then column, as per [`iter_area`](#method.iter_area).
+    ///
+    /// `buf` keeps its capacity, so reusing it, such as to render each frame, avoids allocating once it has grown to fit the largest area read. If the two areas do not intersect, `buf` is left empty.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(5, 4));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// sparse_map.tile_type_at_local_remove(Position::new(2, 1));
+    ///
+    /// let mut buf = Vec::new();
+    /// sparse_map.read_area_into(Area::new(Position::new(1, 0), Size::new(3, 2)), &mut buf);
+    /// assert!(buf.len() == 6);
+    /// assert!(
+    ///     buf == vec![
+    ///         Some(TileType::Wall),
+    ///         Some(TileType::Wall),
+    ///         Some(TileType::Wall),
+    ///         Some(TileType::Floor),
+    ///         None,
+    ///         Some(TileType::Floor),
+    ///     ]
+    /// );
+    ///
+    /// let capacity = buf.capacity();
+    /// sparse_map.read_area_into(Area::new(Position::new(3, 2), Size::new(4, 4)), &mut buf);
+    /// assert!(buf == vec![Some(TileType::Floor), Some(TileType::Wall), Some(TileType::Wall), Some(TileType::Wall)]);
+    /// assert!(buf.capacity() == capacity);
+    ///
+    /// sparse_map.read_area_into(Area::new(Position::new(9, 9), Size::new(2, 2)), &mut buf);
+    /// assert!(buf.is_empty());
+    /// ```
+    pub fn read_area_into(&self, area: Area, buf: &mut Vec<Option<TileType>>) {
+        buf.clear();
+        buf.extend(self.iter_area(area).map(|(_, tile_type)| tile_type));
+    }
+
+    /// Recomputes this `SparseMap`'s size to fit the tiles stored directly in it, in any layer, and the current areas of its sub-maps, as per [`sub_map_areas`](#method.sub_map_areas), which may have changed since they were added.
+    ///
+    /// `add_sub_map` grows the area to fit a sub-map only when it is added; if the sub-map later grows, or shrinks, or is unregistered, the area is left stale. This rescans every sub-map, skipping those which are not registered, and sets the size to the smallest which covers them all, and every stored tile, from the area's current `Position`, so the size may shrink as well as grow. The `Position` itself is not changed, as doing so would change which tile each local `Position` reads; anything to the left of, or above, the area is not covered.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 2), TileType::Floor);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Wall);
+    /// sparse_map.add_sub_map(Position::new(3, 3), sub_map);
+    /// assert!(*sparse_map.size() == Size::new(5, 5));
+    ///
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(4, 2), TileType::Floor);
+    /// assert!(*sparse_map.size() == Size::new(5, 5));
+    /// sparse_map.recompute_area_from_sub_maps();
+    /// assert!(*sparse_map.size() == Size::new(8, 6));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(7, 5)) == Some(TileType::Floor));
+    ///
+    /// unregister_map(sub_map);
+    /// sparse_map.recompute_area_from_sub_maps();
+    /// assert!(*sparse_map.size() == Size::new(2, 2));
+    /// ```
+    pub fn recompute_area_from_sub_maps(&mut self) {
+        let self_position = *self.position();
+        let mut right = 0;
+        let mut bottom = 0;
+        for key in self
+            .tiles
+            .keys()
+            .chain(self.layers.values().flat_map(|tiles| tiles.keys()))
+        {
+            let position = *key - self_position;
+            right = right.max(position.x() + 1);
+            bottom = bottom.max(position.y() + 1);
+        }
+        for (_, area) in self.sub_map_areas() {
+            if area.width() == 0 || area.height() == 0 {
+                continue;
+            }
+
+            let position = *area.position() - self_position;
+            right = right.max(position.x() + area.width() as i32);
+            bottom = bottom.max(position.y() + area.height() as i32);
+        }
+
+        *self.size_mut() = Size::new(right.max(0) as u32, bottom.max(0) as u32);
+    }
+
+    /// Removes and returns the `Portal` at `index`, or `None` if there is no such `Portal`; its tile is reset to `TileType::Floor`, unless another `Portal` remains at the same local `Position`.
+    ///
+    /// As the `Portal`s are stored in order, removal shifts every later `Portal` down by one index; indices obtained before the removal should not be reused. Use [`remove_portal_at_with`](#method.remove_portal_at_with) to choose the tile the `Portal` leaves behind.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let targets = [SparseMap::new(), SparseMap::new(), SparseMap::new()];
+    /// let mut sparse_map = SparseMap::default();
+    /// for (x, target) in targets.iter().enumerate() {
+    ///     let local_position = Position::new(x as i32, 0);
+    ///     sparse_map.add_portal(local_position, CardinalDirection::North, Position::zero(), *target);
+    /// }
+    ///
+    /// let removed = sparse_map.remove_portal_at(1).unwrap();
+    /// assert!(removed.target() == targets[1]);
+    /// assert!(sparse_map.portal_count() == 2);
+    /// assert!(sparse_map.get_portal_at(0).unwrap().target() == targets[0]);
+    /// assert!(sparse_map.get_portal_at(1).unwrap().target() == targets[2]);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 0)) == Some(TileType::Floor));
+    /// assert!(sparse_map.remove_portal_at(2).is_none());
+    ///
+    /// let removed = sparse_map.remove_portal_at_position(Position::new(2, 0)).unwrap();
+    /// assert!(removed.target() == targets[2]);
+    /// assert!(sparse_map.portal_count() == 1);
+    /// assert!(sparse_map.remove_portal_at_position(Position::new(2, 0)).is_none());
+    ///
+    /// sparse_map.remove_portal_at_with(0, None);
+    /// assert!(sparse_map.portal_count() == 0);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 0)) == Some(TileType::Portal));
+    /// ```
+    pub fn remove_portal_at(&mut self, index: usize) -> Option<Portal> {
+        self.remove_portal_at_with(index, Some(TileType::Floor))
+    }
+
+    /// Removes and returns the first `Portal` at `local_position`, or `None` if there is no such `Portal`; its tile is reset to `TileType::Floor`, unless another `Portal` remains at the same local `Position`.
+    ///
+    /// As with [`remove_portal_at`](#method.remove_portal_at), every later `Portal` shifts down by one index.
+    pub fn remove_portal_at_position(&mut self, local_position: Position) -> Option<Portal> {
+        self.remove_portal_at_position_with(local_position, Some(TileType::Floor))
+    }
+
+    /// Removes and returns the first `Portal` at `local_position`, or `None` if there is no such `Portal`; its tile is reset to `reset_tile_type`, unless another `Portal` remains at the same local `Position`, or `reset_tile_type` is `None`.
+    ///
+    /// As with [`remove_portal_at`](#method.remove_portal_at), every later `Portal` shifts down by one index.
+    pub fn remove_portal_at_position_with(
+        &mut self,
+        local_position: Position,
+        reset_tile_type: Option<TileType>,
+    ) -> Option<Portal> {
+        let index = self
+            .portals
+            .iter()
+            .position(|portal| *portal.local_position() == local_position)?;
+        self.remove_portal_at_with(index, reset_tile_type)
+    }
+
+    /// Removes and returns the `Portal` at `index`, or `None` if there is no such `Portal`; its tile is reset to `reset_tile_type`, unless another `Portal` remains at the same local `Position`, or `reset_tile_type` is `None`.
+    ///
+    /// As with [`remove_portal_at`](#method.remove_portal_at), every later `Portal` shifts down by one index.
+    pub fn remove_portal_at_with(
+        &mut self,
+        index: usize,
+        reset_tile_type: Option<TileType>,
+    ) -> Option<Portal> {
+        if index >= self.portals.len() {
+            return None;
+        }
+
+        let portal = self.portals.remove(index);
+        self.portal_kinds.remove(index);
+        let local_position = *portal.local_position();
+        if let Some(reset_tile_type) = reset_tile_type {
+            if !self
+                .portals
+                .iter()
+                .any(|other| *other.local_position() == local_position)
+            {
+                self.tile_type_at_local_set(local_position, reset_tile_type);
+            }
+        }
+
+        Some(portal)
+    }
+
+    /// Removes and returns the sub-map at `index`, or `None` if there is no such sub-map, and recomputes the area, as per [`recompute_area_from_sub_maps`](#method.recompute_area_from_sub_maps).
+    ///
+    /// The removed sub-map's tiles are no longer resolved through `tile_type_at_local`, and the map itself stays registered. As with [`remove_portal_at`](#method.remove_portal_at), every later sub-map shifts down by one index.
+    ///
+    /// See [`set_sub_map_target`](#method.set_sub_map_target) for an example.
+    pub fn remove_sub_map_at(&mut self, index: usize) -> Option<SubMap> {
+        if index >= self.sub_maps.len() {
+            return None;
+        }
+
+        let sub_map = self.sub_maps.remove(index);
+        self.recompute_area_from_sub_maps();
+        Some(sub_map)
+    }
+
+    /// Moves, or removes, the tiles of every layer above layer `0`, every door, and all tile data, as given by `remap`, which is passed and returns storage keys, as used for `tiles`.
+    fn remap_layers(&mut self, remap: &dyn Fn(Position) -> Option<Position>) {
+        for tiles in self.layers.values_mut() {
+            *tiles = tiles
+                .drain()
+                .filter_map(|(position, tile_type)| Some((remap(position)?, tile_type)))
+                .collect();
+        }
+        self.doors = self
+            .doors
+            .drain()
+            .filter_map(|(position, open)| Some((remap(position)?, open)))
+            .collect();
+        self.tile_data = self
+            .tile_data
+            .drain()
+            .filter_map(|(position, tile_data)| Some((remap(position)?, tile_data)))
+            .collect();
+    }
+
+    /// Replaces every tile stored directly in this `SparseMap` which is `from` with `to`, and returns the number of tiles replaced.
+    ///
+    /// Only this `SparseMap`'s own tiles are changed; the tiles of its sub-maps are not, and may still be resolved through `tile_type_at_local`. Replacing with `TileType::Void` leaves the tiles stored as `Void`, rather than removing them; use [`compact`](#method.compact) afterwards to remove them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_filled(Size::new(1, 1), TileType::Floor);
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(4, 3));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// sparse_map.add_sub_map(Position::new(5, 0), sub_map);
+    ///
+    /// assert!(sparse_map.replace_tiles(TileType::Floor, TileType::Wall) == 2);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 1)) == Some(TileType::Wall));
+    /// assert!(sparse_map.count_tile_type(TileType::Wall) == 12);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 0)) == Some(TileType::Floor));
+    /// assert!(sparse_map.replace_tiles(TileType::Floor, TileType::Wall) == 0);
+    ///
+    /// assert!(sparse_map.replace_tiles(TileType::Wall, TileType::Void) == 12);
+    /// assert!(sparse_map.tiles_iter().count() == 12);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == None);
+    /// ```
+    pub fn replace_tiles(&mut self, from: TileType, to: TileType) -> usize {
+        let mut output = 0;
+        for tile_type in self.tiles.values_mut() {
+            if *tile_type == from {
+                *tile_type = to;
+                output += 1;
+            }
+        }
+
+        output
+    }
+
+    /// Reserves capacity for at least `size.width() * size.height()` tiles in total, so that filling an area of that size does not repeatedly reallocate this `SparseMap`'s tile storage.
+    ///
+    /// This is only a hint; no tiles are inserted, and this `SparseMap`'s area is left unchanged, growing as tiles are set, as usual.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let area = Area::new(Position::zero(), Size::new(256, 256));
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.reserve(*area.size());
+    /// assert!(sparse_map.tiles_iter().count() == 0);
+    /// assert!(*sparse_map.size() == Size::zero());
+    ///
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// let mut unreserved = SparseMap::default();
+    /// unreserved.fill_area(area, TileType::Floor);
+    /// assert!(sparse_map == unreserved);
+    /// assert!(sparse_map.tiles_iter().count() == 256 * 256);
+    /// ```
+    pub fn reserve(&mut self, size: Size) {
+        let total = size.width() as usize * size.height() as usize;
+        self.tiles.reserve(total.saturating_sub(self.tiles.len()));
+    }
+
+    /// Rotates the facing of the `Portal` at `index` by `rotation`, leaving its local `Position`, and its end-point in its target, unchanged; if there is no such `Portal`, nothing is changed.
+    ///
+    /// Unlike `rotate`, which turns the whole `SparseMap`, along with the facing of every `Portal`, this turns a single facing, such as when editing `Portal`s by hand.
+    ///
+    /// See [`set_portal_facing`](#method.set_portal_facing) for an example.
+    pub fn rotate_portal_facing(&mut self, index: usize, rotation: CardinalRotation) {
+        if let Some(portal) = self.portals.get_mut(index) {
+            *portal.portal_to_map_facing_mut() = *portal.portal_to_map_facing() + rotation;
+        }
+    }
+
+    /// Creates and registers a rotated copy of this `SparseMap`, with a fresh `MapId` from `get_new_map_id`, and returns its `MapId`; this `SparseMap` is left unchanged.
+    ///
+    /// The copy is rotated as per `rotate`, except that, as `rotate` would also rotate sub-maps shared with this `SparseMap`, the copy's sub-maps are first baked in with [`flatten_sub_maps`](#method.flatten_sub_maps); the copy has no sub-maps, and the sub-maps themselves are unchanged. Its `Portal`s, and their `PortalKind`s, are rotated with it.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut template = SparseMap::default();
+    /// template.fill_area(Area::new(Position::zero(), Size::new(3, 2)), TileType::Floor);
+    /// template.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    ///
+    /// let rotated = template.rotated(CardinalRotation::Left90);
+    /// assert!(rotated != template.map_id());
+    /// assert!(template.to_ascii() == "#..\n...");
+    /// assert!(*template.size() == Size::new(3, 2));
+    ///
+    /// let maps = MAPS.read();
+    /// let rotated = maps[rotated].read();
+    /// assert!(*rotated.size() == Size::new(2, 3));
+    /// assert!(rotated.to_ascii() == ".#\n..\n..");
+    /// ```
+    pub fn rotated(&self, rotation: CardinalRotation) -> MapId {
+        let mut sparse_map = self.clone();
+        sparse_map.map_id = get_new_map_id();
+        sparse_map.flatten_sub_maps();
+        sparse_map.rotate(rotation);
+        register_map(sparse_map)
+    }
+
+    /// Creates and registers a new `SparseMap` in which every tile of this `SparseMap` becomes a `factor` by `factor` block of the same tile, and returns its `MapId`; its area is `factor` times the size of this `SparseMap`'s area.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, so the tiles of sub-maps are scaled, and baked in; the new map has no sub-maps. Each `Portal`, along with its `PortalKind`, is copied to the top-left tile of its block, so a `Portal` at local `Position` `(x, y)` is placed at `(x * factor, y * factor)`; its facing, target, and end-point in the target map are unchanged, as the target is not scaled. A `factor` of `1` yields a copy with a fresh `MapId`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is `0`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 0), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 1), TileType::Floor);
+    /// sparse_map.add_portal(Position::new(1, 1), CardinalDirection::East, Position::zero(), target);
+    ///
+    /// let scaled = sparse_map.scale(3);
+    /// let maps = MAPS.read();
+    /// let scaled = maps[scaled].read();
+    /// assert!(*scaled.size() == Size::new(6, 6));
+    /// assert!(scaled.to_ascii() == "###...\n###...\n###...\n...+++\n...+++\n...+++");
+    /// let portal = scaled.get_portal_at(0).unwrap();
+    /// assert!(*portal.local_position() == Position::new(3, 3));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::East);
+    /// # drop(scaled);
+    /// # drop(maps);
+    ///
+    /// let copy = sparse_map.scale(1);
+    /// assert!(MAPS.read()[copy].read().to_ascii() == sparse_map.to_ascii());
+    /// ```
+    pub fn scale(&self, factor: u32) -> MapId {
+        assert!(factor > 0, "a SparseMap cannot be scaled by a factor of 0");
+
+        let factor = factor as i32;
+        let mut sparse_map = Self::default();
+        for (position, tile_type) in self.iter_area(Area::new(Position::zero(), *self.size())) {
+            if let Some(tile_type) = tile_type {
+                for y in 0..factor {
+                    for x in 0..factor {
+                        sparse_map.tiles.insert(
+                            Position::new(position.x() * factor + x, position.y() * factor + y),
+                            tile_type,
+                        );
+                    }
+                }
+            }
+        }
+
+        for (portal, portal_kind) in self.portals.iter().zip(self.portal_kinds.iter()) {
+            let local_position = *portal.local_position();
+            sparse_map.portals.push(Portal::new(
+                Position::new(local_position.x() * factor, local_position.y() * factor),
+                *portal.portal_to_map_facing(),
+                *portal.portal_to_map_position(),
+                portal.target(),
+            ));
+            sparse_map.portal_kinds.push(*portal_kind);
+        }
+
+        *sparse_map.size_mut() =
+            Size::new(self.width() * factor as u32, self.height() * factor as u32);
+        register_map(sparse_map)
+    }
+
+    /// Sets a door at the given local `Position`, which is open if `open` is `true`, and returns the `TileType` previously stored there.
+    ///
+    /// As `TileType` cannot be extended, a door is stored as the tile it behaves as, alongside its state: `TileType::Floor` when open, and `TileType::Wall` when closed. As such, every query which reads tiles, such as `tile_type_at_local`, `to_ascii`, `find_path`, and `line_of_sight`, treats an open door as passable, and a closed one as blocking, and `TileTypeStandardCmp` ranks them as `Floor` and `Wall` respectively. The tile is set through `tile_type_at_local_set`, so the `SparseMap` expands to fit it, and a negative local `Position` is ignored, with no door set. Doors are moved by `flip_horizontal`, `flip_vertical`, `rotate`, `shrink_to_fit`, and `translate`, compared by `eq`, and kept by serialization and snapshots, but are only copied into other new maps, such as by `extract_area` or `to_dense`, as their tiles. Setting, or removing, the tile under a door removes the door, so that a map which once had a door there equals one which never did.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(7, 3));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// let door = Position::new(3, 1);
+    /// sparse_map.set_door(door, false);
+    /// assert!(sparse_map.door_at(door) == Some(false));
+    /// assert!(sparse_map.to_ascii() == "#######\n#..#..#\n#######");
+    ///
+    /// let is_floor = |tile_type| tile_type == TileType::Floor;
+    /// let (from, to) = (Position::new(1, 1), Position::new(5, 1));
+    /// assert!(sparse_map.find_path(from, to, &is_floor).is_none());
+    ///
+    /// assert!(sparse_map.toggle_door(door) == Some(true));
+    /// assert!(sparse_map.door_at(door) == Some(true));
+    /// assert!(sparse_map.tile_type_at_local(door) == Some(TileType::Floor));
+    /// assert!(sparse_map.find_path(from, to, &is_floor).unwrap().len() == 5);
+    ///
+    /// sparse_map.tile_type_at_local_set(door, TileType::Wall);
+    /// assert!(sparse_map.door_at(door) == None);
+    /// assert!(sparse_map.toggle_door(door) == None);
+    /// assert!(sparse_map.door_at(Position::new(1, 1)) == None);
+    ///
+    /// // A door which has been overwritten leaves nothing behind.
+    /// let doorless = sparse_map.clone();
+    /// sparse_map.set_door(door, false);
+    /// sparse_map.tile_type_at_local_set(door, TileType::Wall);
+    /// assert!(sparse_map == doorless);
+    /// assert!(sparse_map.content_hash() == doorless.content_hash());
+    /// ```
+    pub fn set_door(&mut self, pos: Position, open: bool) -> Option<TileType> {
+        if pos.x() < 0 || pos.y() < 0 {
+            return None;
+        }
+
+        let output = self.tile_type_at_local_set(pos, door_tile_type(open));
+        self.doors.insert(pos + *self.position(), open);
+        output
+    }
+
+    /// Sets the tile at the given local `Position` of `layer`, for storing, for example, decoration or fog over the terrain, and returns the tile previously stored there.
+    ///
+    /// Layer `0` is the `SparseMap`'s own tiles, and is set through `tile_type_at_local_set`. Other layers are stored separately, one `HashMap` each, and are neither read by `tile_type_at_local`, nor written through to sub-maps; read them with [`layer_tile`](#method.layer_tile), or merge them with [`composite_tile`](#method.composite_tile). Every layer expands the area, and ignores negative local `Position`s, as per `tile_type_at_local_set`. The layers are moved by `flip_horizontal`, `flip_vertical`, `rotate`, `shrink_to_fit`, and `translate`, compared by `eq`, and kept by serialization and snapshots, but only layer `0` is copied into other new maps, such as by `extract_area` or `to_dense`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Floor);
+    /// assert!(sparse_map.set_layer_tile(1, Position::new(1, 1), TileType::Wall) == None);
+    /// assert!(sparse_map.set_layer_tile(1, Position::new(1, 1), TileType::Portal) == Some(TileType::Wall));
+    ///
+    /// assert!(sparse_map.layer_tile(0, Position::new(1, 1)) == Some(TileType::Floor));
+    /// assert!(sparse_map.layer_tile(1, Position::new(1, 1)) == Some(TileType::Portal));
+    /// assert!(sparse_map.layer_tile(1, Position::new(0, 0)) == None);
+    /// assert!(sparse_map.layer_tile(2, Position::new(1, 1)) == None);
+    /// assert!(sparse_map.composite_tile(Position::new(1, 1)) == Some(TileType::Portal));
+    ///
+    /// sparse_map.set_layer_tile(1, Position::new(4, 0), TileType::Wall);
+    /// assert!(*sparse_map.size() == Size::new(5, 3));
+    /// sparse_map.flip_horizontal();
+    /// assert!(sparse_map.layer_tile(1, Position::new(0, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.layer_tile(1, Position::new(3, 1)) == Some(TileType::Portal));
+    /// ```
+    pub fn set_layer_tile(&mut self, layer: u8, pos: Position, t: TileType) -> Option<TileType> {
+        if layer == 0 {
+            return self.tile_type_at_local_set(pos, t);
+        }
+        if pos.x() < 0 || pos.y() < 0 {
+            return None;
+        }
+
+        *self.size_mut().height_mut() = self.size().height().max(pos.y() as u32 + 1);
+        *self.size_mut().width_mut() = self.size().width().max(pos.x() as u32 + 1);
+        let key = pos + *self.position();
+        self.layers.entry(layer).or_default().insert(key, t)
+    }
+
+    /// Sets the `TileType` which `tile_type_at_local` returns for local `Position`s outside of this `SparseMap`'s area, in place of `None`; for example, `TileType::Wall` makes the edge of the map impassable to a pathfinder.
+    ///
+    /// By default, no out-of-bounds tile is set. The area is unaffected, so `intersects_local_position` still reports positions outside of it as not intersecting, and `tile_type_at_local_set` still expands the `SparseMap` to fit.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Floor);
+    /// assert!(sparse_map.out_of_bounds_tile() == None);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(100, -100)) == None);
+    ///
+    /// sparse_map.set_out_of_bounds_tile(TileType::Wall);
+    /// assert!(sparse_map.out_of_bounds_tile() == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(100, -100)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 2)) == Some(TileType::Floor));
+    /// assert!(!sparse_map.intersects_local_position(Position::new(3, 0)));
+    /// ```
+    pub fn set_out_of_bounds_tile(&mut self, tile_type: TileType) {
+        self.out_of_bounds_tile = Some(tile_type);
+    }
+
+    /// Sets the facing of the `Portal` at `index` to `facing`, leaving its local `Position`, its end-point in its target, and its `PortalKind` unchanged; if there is no such `Portal`, nothing is changed.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portal(Position::new(1, 0), CardinalDirection::North, Position::zero(), target);
+    ///
+    /// sparse_map.rotate_portal_facing(0, CardinalRotation::Right90);
+    /// assert!(*sparse_map.get_portal_at(0).unwrap().portal_to_map_facing() == CardinalDirection::East);
+    /// sparse_map.rotate_portal_facing(0, CardinalRotation::Full180);
+    /// assert!(*sparse_map.get_portal_at(0).unwrap().portal_to_map_facing() == CardinalDirection::West);
+    ///
+    /// sparse_map.set_portal_facing(0, CardinalDirection::South);
+    /// let portal = sparse_map.get_portal_at(0).unwrap();
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::South);
+    /// assert!(*portal.local_position() == Position::new(1, 0));
+    /// assert!(portal.target() == target);
+    ///
+    /// sparse_map.set_portal_facing(1, CardinalDirection::East);
+    /// assert!(sparse_map.portal_count() == 1);
+    /// ```
+    pub fn set_portal_facing(&mut self, index: usize, facing: CardinalDirection) {
+        if let Some(portal) = self.portals.get_mut(index) {
+            *portal.portal_to_map_facing_mut() = facing;
+        }
+    }
+
+    /// Replaces the map targeted by the sub-map at `index` with `new_target`, keeping its local `Position`, and recomputes the area, as per [`recompute_area_from_sub_maps`](#method.recompute_area_from_sub_maps); if there is no such sub-map, nothing is changed.
+    ///
+    /// The tiles of `new_target` are resolved through `tile_type_at_local` in place of those of the old target, which stays registered.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let floor = SparseMap::new_filled(Size::new(2, 2), TileType::Floor);
+    /// let wall = SparseMap::new_filled(Size::new(2, 2), TileType::Wall);
+    /// let portal = SparseMap::new_filled(Size::new(3, 1), TileType::Portal);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_sub_map(Position::zero(), floor);
+    /// sparse_map.add_sub_map(Position::new(3, 0), wall);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(4, 1)) == Some(TileType::Wall));
+    ///
+    /// sparse_map.set_sub_map_target(1, portal);
+    /// assert!(sparse_map.get_sub_map_at(1).unwrap().value() == portal);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 0)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 0)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(4, 1)) == None);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Floor));
+    /// assert!(*sparse_map.size() == Size::new(6, 2));
+    ///
+    /// assert!(sparse_map.remove_sub_map_at(0).unwrap().value() == floor);
+    /// assert!(sparse_map.remove_sub_map_at(1).is_none());
+    /// assert!(sparse_map.sub_map_count() == 1);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 0)) == None);
+    /// assert!(*sparse_map.size() == Size::new(6, 1));
+    /// ```
+    pub fn set_sub_map_target(&mut self, index: usize, new_target: MapId) {
+        let sub_map = match self.sub_maps.get_mut(index) {
+            Some(sub_map) => sub_map,
+            None => return,
+        };
+
+        *sub_map = SubMap::new(*sub_map.local_position(), new_target);
+        self.recompute_area_from_sub_maps();
+    }
+
+    /// Attaches `tile_data` to the tile at the given local `Position`, and returns the `TileData` previously attached there.
+    ///
+    /// Tile data is stored separately from the tiles, and never affects `tile_type_at_local`; it may be attached wherever a tile might be, whether or not one is stored there, but does not expand the area, and is ignored for negative local `Position`s. It is kept when the tile there is replaced or removed; use [`tile_data_remove`](#method.tile_data_remove) to remove it too. Tile data is moved by `flip_horizontal`, `flip_vertical`, `rotate`, `shrink_to_fit`, and `translate`, compared by `eq`, and kept by serialization and snapshots, but is not copied into other new maps, such as by `extract_area` or `to_dense`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let trap = Position::new(2, 1);
+    /// sparse_map.tile_type_at_local_set(trap, TileType::Floor);
+    /// assert!(sparse_map.set_tile_data(trap, TileData::new().with("damage", 5)) == None);
+    /// assert!(sparse_map.tile_data(trap).unwrap().get("damage") == Some(5));
+    /// assert!(sparse_map.tile_type_at_local(trap) == Some(TileType::Floor));
+    ///
+    /// sparse_map.tile_data_mut(trap).unwrap().set("damage", 8);
+    /// assert!(sparse_map.tile_data(trap).unwrap().get("damage") == Some(8));
+    ///
+    /// // Removing the tile keeps its data, unless that is removed too.
+    /// sparse_map.tile_type_at_local_remove(trap);
+    /// assert!(sparse_map.tile_data(trap).is_some());
+    /// assert!(sparse_map.tile_data_remove(trap).unwrap().get("damage") == Some(8));
+    /// assert!(sparse_map.tile_data(trap) == None);
+    ///
+    /// assert!(sparse_map.set_tile_data(Position::new(-1, 0), TileData::new()) == None);
+    /// assert!(sparse_map.tile_data(Position::new(-1, 0)) == None);
+    /// ```
+    pub fn set_tile_data(&mut self, pos: Position, tile_data: TileData) -> Option<TileData> {
+        if pos.x() < 0 || pos.y() < 0 {
+            return None;
+        }
+
+        self.tile_data.insert(pos + *self.position(), tile_data)
+    }
+
+    /// Sets a callback which is called every time a tile is set through `tile_type_at_local_set`, after it is stored, with its local `Position`, the `TileType` previously stored there, and the new `TileType`, for example to update a renderer incrementally.
+    ///
+    /// The observer replaces any previously set. It is also called by methods which set tiles through `tile_type_at_local_set`, such as `fill_area`, `blit`, and `set_door`, but not by those which rewrite the stored tiles wholesale, such as `replace_tiles`, `rotate`, or `translate`, nor for tiles written through to sub-maps, nor for negative local `Position`s, which are ignored. Until an observer is set, setting a tile costs no more than an `Option` check. Clones of this `SparseMap` share the observer.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let changes = Arc::new(Mutex::new(Vec::new()));
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    ///
+    /// let recorded = changes.clone();
+    /// sparse_map.set_tile_observer(Arc::new(move |position, old, new| {
+    ///     recorded.lock().unwrap().push((position, old, new));
+    /// }));
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(-1, 0), TileType::Wall);
+    /// sparse_map.fill_area(Area::new(Position::new(2, 1), Size::new(2, 1)), TileType::Floor);
+    ///
+    /// assert!(
+    ///     *changes.lock().unwrap()
+    ///         == vec![
+    ///             (Position::new(0, 0), Some(TileType::Wall), TileType::Floor),
+    ///             (Position::new(2, 1), None, TileType::Wall),
+    ///             (Position::new(2, 1), Some(TileType::Wall), TileType::Floor),
+    ///             (Position::new(3, 1), None, TileType::Floor),
+    ///         ]
+    /// );
+    /// ```
+    pub fn set_tile_observer(&mut self, observer: TileObserver) {
+        self.tile_observer = Some(observer);
+    }
+
+    /// Sets the comparison which `tile_type_at_local` uses to choose between the tiles of this `SparseMap` and its sub-maps, in place of `TileTypeStandardCmp`; it is called as per `tile_type_at_local_sort_by`, and returns `Ordering::Less` if the right-hand tile should win.
+    ///
+    /// Clones of this `SparseMap` share the comparison. Use [`set_tile_priority_cmp`](#method.set_tile_priority_cmp) to use a `TileTypeCmp` implementation instead.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # use std::sync::Arc;
+    /// let sub_map = SparseMap::new();
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    ///
+    /// // As per `TileTypeStandardCmp`, `Floor` wins over `Wall`.
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+    ///
+    /// sparse_map.set_tile_priority(Arc::new(|left, right| {
+    ///     let priority = |tile_type: &Option<TileType>| match tile_type {
+    ///         Some(TileType::Wall) => 2,
+    ///         Some(TileType::Floor) => 1,
+    ///         _ => 0,
+    ///     };
+    ///     priority(left).cmp(&priority(right))
+    /// }));
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    ///
+    /// sparse_map.set_tile_priority_cmp::<TileTypeStandardCmp>();
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+    /// ```
+    pub fn set_tile_priority(&mut self, sort_best: TilePriority) {
+        self.tile_priority = Some(sort_best);
+    }
+
+    /// Sets `TTileTypeCmp` as the priority which `tile_type_at_local` uses to choose between the tiles of this `SparseMap` and its sub-maps, in place of `TileTypeStandardCmp`.
+    ///
+    /// See [`set_tile_priority`](#method.set_tile_priority) for an example.
+    pub fn set_tile_priority_cmp<TTileTypeCmp>(&mut self)
+    where
+        TTileTypeCmp: TileTypeCmp,
+    {
+        self.tile_priority = Some(Arc::new(|left, right| {
+            TTileTypeCmp::cmp_option(right, left)
+        }));
+    }
+
+    /// Shrinks the `SparseMap`'s area to the tightest bounding box around its stored tiles, `Portal`s, and sub-maps, moving them all so that the box starts at local `Position::zero()`; the `SparseMap`'s own `Position` is unchanged.
+    ///
+    /// Stored `TileType::Void` tiles do not count towards the box, and those which fall outside it are discarded; as such, a `SparseMap` which is empty, or holds only `TileType::Void` tiles, shrinks to a zero size. `Portal`s on other maps which lead into this `SparseMap` are not updated.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(10, 10)), TileType::Floor);
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(10, 9)), TileType::Void);
+    /// sparse_map.fill_area(Area::new(Position::new(0, 9), Size::new(9, 1)), TileType::Void);
+    /// assert!(*sparse_map.size() == Size::new(10, 10));
+    ///
+    /// sparse_map.shrink_to_fit();
+    /// assert!(*sparse_map.size() == Size::new(1, 1));
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == Some(TileType::Floor));
+    /// assert!(sparse_map.tiles_iter().count() == 1);
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::zero(), TileType::Void);
+    /// sparse_map.shrink_to_fit();
+    /// assert!(*sparse_map.size() == Size::zero());
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        let self_position = *self.position();
+        let mut extents: Vec<(Position, Position)> = self
+            .tiles
+            .iter()
+            .filter(|(_, tile_type)| **tile_type != TileType::Void)
+            .map(|(position, _)| (*position - self_position, *position - self_position))
+            .chain(
+                self.portals
+                    .iter()
+                    .map(|portal| (*portal.local_position(), *portal.local_position())),
+            )
+            .collect();
+        if !self.sub_maps.is_empty() {
+            let maps = MAPS.read();
+            for sub_map in self.sub_maps.iter() {
+                let sub_map_size = *maps[sub_map.value()].read().size();
+                if sub_map_size.width() == 0 || sub_map_size.height() == 0 {
+                    continue;
+                }
+
+                let top_left = self.sub_map_offset(sub_map);
+                let bottom_right = top_left
+                    + Position::new(
+                        sub_map_size.width() as i32 - 1,
+                        sub_map_size.height() as i32 - 1,
+                    );
+                extents.push((top_left, bottom_right));
+            }
+        }
+
+        let (min, max) = match extents.into_iter().reduce(|(min, max), (low, high)| {
+            (
+                Position::new(min.x().min(low.x()), min.y().min(low.y())),
+                Position::new(max.x().max(high.x()), max.y().max(high.y())),
+            )
+        }) {
+            Some(bounds) => bounds,
+            None => {
+                self.tiles.clear();
+                self.layers.clear();
+                self.doors.clear();
+                self.tile_data.clear();
+                *self.size_mut() = Size::zero();
+                return;
+            }
+        };
+
+        let is_within = |position: Position| {
+            let local_position = position - self_position;
+            local_position.x() >= 0
+                && local_position.y() >= 0
+                && local_position.x() <= max.x() - min.x()
+                && local_position.y() <= max.y() - min.y()
+        };
+        self.tiles = self
+            .tiles
+            .drain()
+            .map(|(position, tile_type)| (position - min, tile_type))
+            .filter(|(position, _)| is_within(*position))
+            .collect();
+        self.remap_layers(&|position| Some(position - min).filter(|position| is_within(*position)));
+        for portal in self.portals.iter_mut() {
+            *portal.local_position_mut() = *portal.local_position() - min;
+        }
+        for sub_map in self.sub_maps.iter_mut() {
+            *sub_map.local_position_mut() = *sub_map.local_position() - min;
+        }
+        *self.size_mut() = Size::new(
+            (max.x() - min.x() + 1) as u32,
+            (max.y() - min.y() + 1) as u32,
+        );
+    }
+
+    /// Runs a single generation of cellular-automata cave smoothing over this `SparseMap`'s area, treating local `Position`s outside of the area as `TileType::Wall`.
+    ///
+    /// See [`smooth_walls_with`](#method.smooth_walls_with) for the rules applied.
+    pub fn smooth_walls(&mut self, birth: u8, survive: u8) {
+        self.smooth_walls_with(birth, survive, true);
+    }
+
+    /// Runs a single generation of cellular-automata cave smoothing over this `SparseMap`'s area.
+    ///
+    /// For each local `Position` within the area, the 8-connected neighbors which resolve to `TileType::Wall` are counted, along with, if `out_of_bounds_is_wall` is `true`, those outside of the area. A `TileType::Wall` remains a wall if the count is at least `survive`, and otherwise becomes `TileType::Floor`; a `TileType::Floor` becomes a wall if the count is at least `birth`. Other tiles, and positions with no tile, are left alone.
+    ///
+    /// The next generation is computed entirely from the current one before any tile is written, so changes do not cascade within a single pass. This takes read locks on each sub-map, and then write locks to write the new tiles through to them, and so must not be called while holding a lock on any of them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let to_tile_type = |c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     _ => None,
+    /// };
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(
+    ///     SparseMap::from_ascii("#####\n#...#\n#.#.#\n#...#\n#####", &to_tile_type),
+    ///     Position::zero(),
+    ///     false,
+    /// );
+    /// let unsmoothed = sparse_map.clone();
+    ///
+    /// sparse_map.smooth_walls(5, 4);
+    /// assert!(sparse_map.to_ascii() == "#####\n##.##\n#...#\n##.##\n#####");
+    ///
+    /// let mut sparse_map = unsmoothed;
+    /// sparse_map.smooth_walls_with(5, 4, false);
+    /// assert!(sparse_map.to_ascii() == ".....\n.#.#.\n.....\n.#.#.\n.....");
+    /// ```
+    pub fn smooth_walls_with(&mut self, birth: u8, survive: u8, out_of_bounds_is_wall: bool) {
+        let mut next_generation = Vec::new();
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let position = Position::new(x, y);
+                let tile_type = match self.tile_type_at_local(position) {
+                    Some(tile_type @ TileType::Wall) | Some(tile_type @ TileType::Floor) => {
+                        tile_type
+                    }
+                    _ => continue,
+                };
+
+                let walls = self
+                    .neighbors8(position)
+                    .iter()
+                    .filter(|(neighbor, neighbor_tile_type)| {
+                        *neighbor_tile_type == Some(TileType::Wall)
+                            || (out_of_bounds_is_wall
+                                && !self.size().intersects_local_position(*neighbor))
+                    })
+                    .count();
+                let next_tile_type = match tile_type {
+                    TileType::Wall if walls < survive as usize => TileType::Floor,
+                    TileType::Floor if walls >= birth as usize => TileType::Wall,
+                    _ => continue,
+                };
+                next_generation.push((position, next_tile_type));
+            }
+        }
+
+        for (position, tile_type) in next_generation {
+            self.tile_type_at_local_set(position, tile_type);
+        }
+    }
+
+    /// Writes every tile of the `template` map into this `SparseMap`, as per [`blit`](#method.blit), but rotated by `rotation` as it is written; `template` itself is left unchanged, unlike with `rotate`.
+    ///
+    /// Each tile's local `Position` within `template` is rotated as per `rotate`, and then offset by `at`, so that the rotated tiles occupy the area from `at` to the right and bottom, as they would after `rotate`. Positions with no tile are skipped; `TileType::Void` tiles are written only if `overwrite_void` is `true`. `template`'s sub-maps are resolved as tiles, and its `Portal`s are not copied.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let template = SparseMap::from_ascii("#..\n...", &|c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     _ => None,
+    /// });
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.stamp_rotated(template, Position::new(1, 1), CardinalRotation::Right90, false);
+    /// assert!(*sparse_map.size() == Size::new(3, 4));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 3)) == Some(TileType::Wall));
+    /// assert!(sparse_map.count_tile_type(TileType::Floor) == 5);
+    /// assert!(sparse_map.tile_type_at_local(Position::zero()) == None);
+    /// assert!(sparse_map.to_ascii() == "   \n ..\n ..\n #.");
+    /// assert!(MAPS.read()[template].read().tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    ///
+    /// sparse_map.stamp_rotated(template, Position::new(3, 0), CardinalRotation::Full180, false);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 1)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 0)) == Some(TileType::Floor));
+    /// ```
+    pub fn stamp_rotated(
+        &mut self,
+        template: MapId,
+        at: Position,
+        rotation: CardinalRotation,
+        overwrite_void: bool,
+    ) {
+        let mut template_tiles = Vec::new();
+        {
+            let maps = MAPS.read();
+            let template = maps[template].read();
+            let adjust_position = rotation_adjustment(*template.size(), rotation);
+            for y in 0..template.height() as i32 {
+                for x in 0..template.width() as i32 {
+                    let position = Position::new(x, y);
+                    let rotated_position = position * rotation + adjust_position;
+                    match template.tile_type_at_local_sort_by(position, &void_visible_cmp) {
+                        Some(TileType::Void) if !overwrite_void => {}
+                        Some(tile_type) => template_tiles.push((rotated_position, tile_type)),
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        for (position, tile_type) in template_tiles {
+            self.tile_type_at_local_set(at + position, tile_type);
+        }
+    }
+
+    /// Returns the `MapId` of each sub-map, in order, along with the area it covers, in the same space as this `SparseMap`'s own area, including its `Position`.
+    ///
+    /// Each area is the sub-map's size, placed at the sub-map's stored `Position`, which is where `tile_type_at_local` resolves the sub-map's local `(0, 0)`; the sub-map's own `Position` does not move its tiles, so it is not included. As sub-map `Position`s are stored relative to this `SparseMap`'s `Position`, as per [`translate`](#method.translate), they already include it; a sub-map added at local `Position` `(5, 5)` of a `SparseMap` at `(10, 20)` has an area starting at `(15, 25)`. Sub-maps which are not registered are left out.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_room(Size::new(3, 3));
+    /// let removed = SparseMap::new();
+    /// unregister_map(removed);
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_sub_map(Position::new(5, 5), sub_map);
+    /// sparse_map.add_sub_map(Position::zero(), removed);
+    /// assert!(sparse_map.sub_map_areas() == vec![(sub_map, Area::new(Position::new(5, 5), Size::new(3, 3)))]);
+    ///
+    /// sparse_map.translate(Position::new(10, 20));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(5, 5)) == Some(TileType::Wall));
+    /// assert!(sparse_map.sub_map_areas() == vec![(sub_map, Area::new(Position::new(15, 25), Size::new(3, 3)))]);
+    ///
+    /// // A sub-map's own `Position` does not move its tiles, nor its area.
+    /// let mut translated = SparseMap::default();
+    /// translated.fill_area(Area::new(Position::zero(), Size::new(2, 2)), TileType::Floor);
+    /// translated.translate(Position::new(3, 3));
+    /// let translated = register_map(translated);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_sub_map(Position::new(0, 4), translated);
+    /// assert!(sparse_map.sub_map_areas() == vec![(translated, Area::new(Position::new(0, 4), Size::new(2, 2)))]);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 5)) == Some(TileType::Floor));
+    /// sparse_map.recompute_area_from_sub_maps();
+    /// assert!(*sparse_map.size() == Size::new(2, 6));
+    /// ```
+    pub fn sub_map_areas(&self) -> Vec<(MapId, Area)> {
+        let maps = MAPS.read();
+        self.sub_maps
+            .iter()
+            .filter_map(|sub_map| {
+                let map = maps.get(sub_map.value())?.read();
+                if map.map_id() != sub_map.value() {
+                    return None;
+                }
+
+                let position = self.sub_map_offset(sub_map) + *self.position();
+                Some((sub_map.value(), Area::new(position, *map.size())))
+            })
+            .collect()
+    }
+
+    /// Returns the local `Position` of this `SparseMap` at which `sub_map`'s local `(0, 0)` is resolved, as per `tile_type_at_local`.
+    fn sub_map_offset(&self, sub_map: &SubMap) -> Position {
+        *sub_map.local_position() - *self.position()
+    }
+
+    /// Removes the tile stored directly in this `SparseMap` at every local `Position` where the `other` map, offset by `offset`, has a tile other than `TileType::Void`, as per [`tile_type_at_local_remove`](#method.tile_type_at_local_remove).
+    ///
+    /// The tiles are removed, rather than set to `TileType::Void`, so the holes read as `None`; the `SparseMap`'s area is left unchanged, and the tiles of its sub-maps and layers are not removed, and may still be resolved through `tile_type_at_local`. `other`'s tiles are read through `tile_type_at_local`, resolving its sub-maps, before any are removed, so `other` may be a sub-map of this `SparseMap`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut stamp = SparseMap::default();
+    /// stamp.fill_area(Area::new(Position::zero(), Size::new(3, 2)), TileType::Floor);
+    /// stamp.tile_type_at_local_set(Position::new(2, 0), TileType::Void);
+    /// stamp.tile_type_at_local_remove(Position::new(2, 1));
+    /// let stamp = register_map(stamp);
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(5, 5)), TileType::Wall);
+    /// sparse_map.subtract(stamp, Position::new(1, 2));
+    /// assert!(sparse_map.to_ascii() == "#####\n#####\n#  ##\n#  ##\n#####");
+    /// assert!(sparse_map.tile_count() == 25 - 4);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 2)) == None);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(3, 2)) == Some(TileType::Wall));
+    /// assert!(*sparse_map.size() == Size::new(5, 5));
+    /// ```
+    pub fn subtract(&mut self, other: MapId, offset: Position) {
+        let mut other_positions = Vec::new();
+        {
+            let maps = MAPS.read();
+            let other = maps[other].read();
+            for y in 0..other.height() as i32 {
+                for x in 0..other.width() as i32 {
+                    let position = Position::new(x, y);
+                    match other.tile_type_at_local(position) {
+                        Some(TileType::Void) | None => {}
+                        Some(_) => other_positions.push(position + offset),
+                    }
+                }
+            }
+        }
+
+        for position in other_positions {
+            self.tile_type_at_local_remove(position);
+        }
+    }
+
+    /// Returns the number of tiles stored directly in this `SparseMap`, including those set to `TileType::Void`, but not those of its layers, nor of its sub-maps.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 2), TileType::Floor);
+    /// let mut sparse_map = SparseMap::default();
+    /// assert!(sparse_map.tile_count() == 0);
+    ///
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 3)), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(5, 0), TileType::Void);
+    /// sparse_map.set_layer_tile(1, Position::zero(), TileType::Portal);
+    /// sparse_map.add_sub_map(Position::new(6, 0), sub_map);
+    /// assert!(sparse_map.tile_count() == 12 + 1);
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// assert!(sparse_map.tile_count() == 13);
+    /// ```
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns the `TileData` attached to the tile at the given local `Position`, or `None` if there is none.
+    ///
+    /// See [`set_tile_data`](#method.set_tile_data) for an example.
+    pub fn tile_data(&self, pos: Position) -> Option<&TileData> {
+        self.tile_data.get(&(pos + *self.position()))
+    }
+
+    /// Returns a mutable reference to the `TileData` attached to the tile at the given local `Position`, or `None` if there is none.
+    ///
+    /// See [`set_tile_data`](#method.set_tile_data) for an example.
+    pub fn tile_data_mut(&mut self, pos: Position) -> Option<&mut TileData> {
+        let key = pos + *self.position();
+        self.tile_data.get_mut(&key)
+    }
+
+    /// Removes the `TileData` attached to the tile at the given local `Position`, and returns it, or `None` if there was none; the tile itself is unchanged.
+    ///
+    /// See [`set_tile_data`](#method.set_tile_data) for an example.
+    pub fn tile_data_remove(&mut self, pos: Position) -> Option<TileData> {
+        self.tile_data.remove(&(pos + *self.position()))
+    }
+
+    /// Returns the number of tiles stored directly in this `SparseMap` of each `TileType`, in the order `Void`, `Wall`, `Floor`, `Portal`, leaving out any `TileType` with no tiles.
+    ///
+    /// As `TileType` does not implement `Hash`, the counts are returned as pairs, rather than as a `HashMap`. As with [`count_tiles`](#method.count_tiles), stored `TileType::Void` tiles are counted, while positions with no tile, and the tiles of sub-maps and layers, are not; the counts sum to [`tile_count`](#method.tile_count).
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 1), TileType::Portal);
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(5, 4));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Void);
+    /// sparse_map.tile_type_at_local_set(Position::new(7, 7), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::new(1, 1), sub_map);
+    ///
+    /// assert!(
+    ///     sparse_map.tile_histogram()
+    ///         == vec![(TileType::Void, 1), (TileType::Wall, 14), (TileType::Floor, 6)]
+    /// );
+    /// assert!(sparse_map.tile_histogram_including_sub_maps()
+    ///     == vec![(TileType::Wall, 14), (TileType::Floor, 5), (TileType::Portal, 2)]);
+    /// assert!(SparseMap::default().tile_histogram().is_empty());
+    /// ```
+    pub fn tile_histogram(&self) -> Vec<(TileType, usize)> {
+        tile_histogram_of(self.tiles.values().copied())
+    }
+
+    /// Returns the number of local `Position`s within this `SparseMap`'s area whose tile, as resolved through `tile_type_at_local`, is of each `TileType`, as per [`tile_histogram`](#method.tile_histogram).
+    ///
+    /// As with [`count_tiles_including_sub_maps`](#method.count_tiles_including_sub_maps), tiles provided by sub-maps are counted, while positions that resolve to no tile, including those which store `TileType::Void`, are not.
+    ///
+    /// See [`tile_histogram`](#method.tile_histogram) for an example.
+    pub fn tile_histogram_including_sub_maps(&self) -> Vec<(TileType, usize)> {
+        tile_histogram_of(
+            (0..self.height() as i32)
+                .flat_map(|y| (0..self.width() as i32).map(move |x| Position::new(x, y)))
+                .filter_map(|position| self.tile_type_at_local(position)),
+        )
+    }
+
+    /// Returns which map provides the tile which `tile_type_at_local` resolves at the given local `Position`, as a [`TileSource`](enum.TileSource.html), for diagnosing overlapping sub-maps.
+    ///
+    /// The tiles are compared as by `tile_type_at_local`: with this `SparseMap`'s tile priority, if one has been set with [`set_tile_priority`](#method.set_tile_priority), or otherwise `TileTypeStandardCmp`; the sub-maps in order, and then this `SparseMap`'s own tile, with earlier tiles winning ties. A tile provided by a sub-map of a sub-map is reported as coming from the outer sub-map.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # use std::sync::Arc;
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 1), TileType::Wall);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Wall);
+    ///
+    /// // As per `TileTypeStandardCmp`, `Floor` wins over `Wall`.
+    /// assert!(sparse_map.tile_source_at_local(Position::new(0, 0)) == TileSource::SelfMap);
+    /// assert!(sparse_map.tile_source_at_local(Position::new(1, 0)) == TileSource::SubMap(sub_map));
+    /// assert!(sparse_map.tile_source_at_local(Position::new(2, 2)) == TileSource::SelfMap);
+    /// assert!(sparse_map.tile_source_at_local(Position::new(1, 1)) == TileSource::None);
+    ///
+    /// sparse_map.set_tile_priority(Arc::new(|left, right| {
+    ///     let priority = |tile_type: &Option<TileType>| match tile_type {
+    ///         Some(TileType::Wall) => 2,
+    ///         Some(TileType::Floor) => 1,
+    ///         _ => 0,
+    ///     };
+    ///     priority(left).cmp(&priority(right))
+    /// }));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 0)) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_source_at_local(Position::new(0, 0)) == TileSource::SubMap(sub_map));
+    /// ```
+    pub fn tile_source_at_local(&self, pos: Position) -> TileSource {
+        if self.out_of_bounds_tile.is_some() && !self.size().intersects_local_position(pos) {
+            return TileSource::SelfMap;
+        }
+
+        // Returns whether `test` replaces `output`, as in `tile_type_at_local` and `tile_type_at_local_sort_by`.
+        let wins = |output: &Option<TileType>, test: &Option<TileType>| match &self.tile_priority {
+            Some(tile_priority) => tile_priority(output, test) == std::cmp::Ordering::Less,
+            None => TileTypeStandardCmp::cmp_option(output, test) == std::cmp::Ordering::Greater,
+        };
+
+        let mut output = None;
+        let mut source = TileSource::None;
+        for_each_resolved_sub_map(self.map_id, &self.sub_maps, |sub_map, map| {
+            let local_position = pos - self.sub_map_offset(sub_map);
+            let test = map.tile_type_at_local(local_position);
+            if wins(&output, &test) {
+                output = test;
+                source = TileSource::SubMap(sub_map.value());
+            }
+        });
+
+        let self_tile_type = self.tiles.get(&(pos + *self.position())).copied();
+        if wins(&output, &self_tile_type) {
+            output = self_tile_type;
+            source = TileSource::SelfMap;
+        }
+
+        match output {
+            Some(_) => source,
+            None => TileSource::None,
+        }
+    }
+
+    /// Returns the `TileType` at the given local `Position`, as per `tile_type_at_local`, but resolving sub-maps at most `max_depth` levels deep; a `max_depth` of `0` reads only the tiles stored directly in this `SparseMap`, and `1` also reads those stored directly in its sub-maps.
+    ///
+    /// The limit is honoured by the sub-maps provided by this crate, such as `SparseMap` and `DenseMap`; other `Map` implementations used as sub-maps resolve their own sub-maps as they see fit.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let nested = SparseMap::new();
+    /// let sub_map = SparseMap::new();
+    /// {
+    ///     let maps = MAPS.read();
+    ///     maps[nested]
+    ///         .write()
+    ///         .tile_type_at_local_set(Position::new(0, 0), TileType::Portal);
+    ///     let mut map = maps[sub_map].write();
+    ///     map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    ///     map.add_sub_map(Position::zero(), nested);
+    /// }
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// sparse_map.add_sub_map(Position::zero(), sub_map);
+    ///
+    /// let position = Position::zero();
+    /// assert!(sparse_map.tile_type_at_local_depth(position, 0) == Some(TileType::Wall));
+    /// assert!(sparse_map.tile_type_at_local_depth(position, 1) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local_depth(position, 2) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(position) == Some(TileType::Portal));
+    /// ```
+    pub fn tile_type_at_local_depth(&self, pos: Position, max_depth: usize) -> Option<TileType> {
+        with_depth_limit(max_depth, || self.tile_type_at_local(pos))
+    }
+
+    /// Removes the tile stored at the given local `Position`, returning the `TileType` it held, or `None` if there was no tile stored there. The size of the `SparseMap` is unchanged, and sub-maps are not affected.
+    ///
+    /// Unlike setting `TileType::Void`, which stores a tile, removal leaves no tile behind; the position is no longer yielded by [`tiles_iter`](#method.tiles_iter), `tile_type_at_local_mut` returns `None` for it, and [`blit`](#method.blit)ting this `SparseMap` with `overwrite_void` will not erase the destination there. Through `TileTypeStandardCmp`, both read as `None` from `tile_type_at_local`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let position = Position::new(1, 1);
+    /// sparse_map.tile_type_at_local_set(position, TileType::Floor);
+    /// assert!(sparse_map.contains_local_position(position) == Containment::Intersects);
+    ///
+    /// assert!(sparse_map.tile_type_at_local_remove(position) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(position) == None);
+    /// assert!(sparse_map.tile_type_at_local_mut(position) == None);
+    /// assert!(sparse_map.contains_local_position(position) == Containment::Disjoint);
+    /// assert!(sparse_map.tile_type_at_local_remove(position) == None);
+    /// assert!(*sparse_map.size() == Size::new(2, 2));
+    /// ```
+    pub fn tile_type_at_local_remove(&mut self, pos: Position) -> Option<TileType> {
+        let key = pos + *self.position();
+        self.doors.remove(&key);
+        self.tiles.remove(&key)
+    }
+
+    /// Returns an iterator over every tile stored directly in this `SparseMap`, as its local `Position` and `TileType`.
+    ///
+    /// Tiles provided by sub-maps are not included, while stored `TileType::Void` tiles are. The order of iteration is unspecified; use [`tiles_iter_sorted`](#method.tiles_iter_sorted) for a deterministic order.
+    pub fn tiles_iter(&self) -> impl Iterator<Item = (Position, TileType)> + '_ {
+        let self_position = *self.position();
+        self.tiles
+            .iter()
+            .map(move |(position, tile_type)| (*position - self_position, *tile_type))
+    }
+
+    /// Returns an iterator over every tile stored directly in this `SparseMap`, as its local `Position` and `TileType`, sorted by row, and then by column.
+    ///
+    /// Tiles provided by sub-maps are not included, while stored `TileType::Void` tiles are.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 1), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 0), TileType::Void);
+    ///
+    /// let tiles: Vec<(Position, TileType)> = sparse_map.tiles_iter_sorted().collect();
+    /// assert!(
+    ///     tiles
+    ///         == vec![
+    ///             (Position::new(1, 0), TileType::Void),
+    ///             (Position::new(0, 1), TileType::Floor),
+    ///             (Position::new(2, 1), TileType::Wall),
+    ///         ]
+    /// );
+    /// assert!(sparse_map.tiles_iter().count() == 3);
+    /// ```
+    pub fn tiles_iter_sorted(&self) -> impl Iterator<Item = (Position, TileType)> {
+        let mut tiles: Vec<(Position, TileType)> = self.tiles_iter().collect();
+        tiles.sort_by_key(|(position, _)| (position.y(), position.x()));
+        tiles.into_iter()
+    }
+
+    /// Creates and registers a new [`DenseMap`](struct.DenseMap.html) with the same area, tiles, `Portal`s, and sub-maps as this `SparseMap`, and returns its `MapId`.
+    ///
+    /// Every local `Position` within the area reads the same from both; tiles stored outside of the area, which a `DenseMap` cannot hold, are dropped.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// for y in 0..4 {
+    ///     for x in 0..5 {
+    ///         let tile_type = if (x + y) % 2 == 0 { TileType::Floor } else { TileType::Wall };
+    ///         sparse_map.tile_type_at_local_set(Position::new(x, y), tile_type);
+    ///     }
+    /// }
+    /// sparse_map.add_portal(Position::new(4, 3), CardinalDirection::East, Position::zero(), target);
+    /// sparse_map.add_sub_map(Position::new(1, 1), target);
+    ///
+    /// let map_id = sparse_map.to_dense();
+    /// let maps = MAPS.read();
+    /// let dense_map = maps[map_id].read();
+    /// assert!(*dense_map.area() == *sparse_map.area());
+    /// assert!(dense_map.portal_count() == 1 && dense_map.sub_map_count() == 1);
+    /// for y in -1..=sparse_map.height() as i32 {
+    ///     for x in -1..=sparse_map.width() as i32 {
+    ///         let position = Position::new(x, y);
+    ///         assert!(dense_map.tile_type_at_local(position) == sparse_map.tile_type_at_local(position));
+    ///     }
+    /// }
+    /// ```
+    pub fn to_dense(&self) -> MapId {
+        let width = self.width() as usize;
+        let mut tiles = vec![TileType::Void; width * self.height() as usize];
+        for (position, tile_type) in self.tiles_iter() {
+            if self.size().intersects_local_position(position) {
+                tiles[position.y() as usize * width + position.x() as usize] = tile_type;
+            }
+        }
+
+        register_map(DenseMap::from_parts(
+            get_new_map_id(),
+            self.area,
+            tiles,
+            self.portals.clone(),
+            self.sub_maps.clone(),
+        ))
+    }
+
+    /// Returns this `SparseMap`'s size, and the tile resolved through `tile_type_at_local` at every local `Position` within its area, in order of row, and then column, with `void_fill` in place of every `Position` with no tile.
+    ///
+    /// The tile at `(x, y)` is at index `y * width + x`, so the grid can be passed as-is to renderers or across FFI. As `tile_type_at_local` reads stored `TileType::Void` tiles as no tile, they are also replaced by `void_fill`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 0), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Portal);
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Void);
+    ///
+    /// let (size, tiles) = sparse_map.to_grid(TileType::Wall);
+    /// assert!(size == Size::new(3, 2));
+    /// assert!(tiles.len() == 6);
+    /// assert!(
+    ///     tiles
+    ///         == vec![
+    ///             TileType::Wall,
+    ///             TileType::Floor,
+    ///             TileType::Wall,
+    ///             TileType::Wall,
+    ///             TileType::Wall,
+    ///             TileType::Portal,
+    ///         ]
+    /// );
+    /// ```
+    pub fn to_grid(&self, void_fill: TileType) -> (Size, Vec<TileType>) {
+        let tiles = self
+            .iter_area(Area::new(Position::zero(), *self.size()))
+            .map(|(_, tile_type)| tile_type.unwrap_or(void_fill))
+            .collect();
+        (*self.size(), tiles)
+    }
+
+    /// Encodes this `SparseMap` as a compact, run-length encoded `String`, for storing many prebuilt maps; decode it with [`from_rle`](#method.from_rle).
+    ///
+    /// The format is a line of text per field, as follows:
+    ///
+    /// * A header, `RLE x y width height`, giving the area.
+    /// * A line, `P x y facing x y target kind`, for each `Portal`, in order, giving its local `Position`, its facing, as one of `N`, `E`, `S`, or `W`, its end-point in its target, its target's `MapId`, and its [`PortalKind`](enum.PortalKind.html), as one of `locked`, `one_way`, `stairs`, or `tagged:` followed by the tag; the kind is left out for `PortalKind::Open`.
+    /// * A line, `S x y target`, for each sub-map, in order, giving its `Position`, as stored, and returned by `SubMap::local_position`, and its target's `MapId`; see [`translate`](#method.translate).
+    /// * A single line of runs, covering the tiles stored directly in this `SparseMap` within its area, in order of row, and then column, with rows following on from each other. Each run is a count, followed by a character for its tile: `'.'` for `TileType::Floor`, `'#'` for `TileType::Wall`, `'+'` for `TileType::Portal`, `'_'` for `TileType::Void`, and `'-'` for no tile.
+    ///
+    /// Large, uniform areas encode as a handful of runs, far smaller than an ASCII grid, or JSON. Tiles provided by sub-maps are not encoded, as the sub-maps themselves are. Unlike serialization, layers, doors, and tile data are not encoded.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let target = SparseMap::new();
+    /// let sub_map = SparseMap::new_filled(Size::new(2, 2), TileType::Wall);
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(50, 50));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// sparse_map.tile_type_at_local_set(Position::new(10, 10), TileType::Void);
+    /// sparse_map.tile_type_at_local_remove(Position::new(11, 10));
+    /// sparse_map.add_portal(Position::new(49, 25), CardinalDirection::East, Position::new(0, 1), target);
+    /// sparse_map.add_portal_with_kind(Position::new(0, 25), CardinalDirection::West, Position::new(0, 1), target, PortalKind::Locked);
+    /// sparse_map.add_sub_map(Position::new(20, 20), sub_map);
+    ///
+    /// let rle = sparse_map.to_rle();
+    /// assert!(rle.starts_with(&format!(
+    ///     "RLE 0 0 50 50\nP 49 25 E 0 1 {}\nP 0 25 W 0 1 {} locked\nS 20 20 {}\n51#48.2#",
+    ///     target, target, sub_map
+    /// )));
+    /// assert!(rle.len() * 5 < sparse_map.to_ascii().len());
+    ///
+    /// let decoded = SparseMap::from_rle(&rle).unwrap();
+    /// let maps = MAPS.read();
+    /// let decoded = maps[decoded].read();
+    /// assert!(decoded.area() == sparse_map.area());
+    /// assert!(decoded.to_ascii() == sparse_map.to_ascii());
+    /// for y in 0..50 {
+    ///     for x in 0..50 {
+    ///         let position = Position::new(x, y);
+    ///         assert!(decoded.tile_type_at_local(position) == sparse_map.tile_type_at_local(position));
+    ///     }
+    /// }
+    /// let portal = decoded.get_portal_at(0).unwrap();
+    /// assert!(decoded.portal_count() == 2);
+    /// assert!(*portal.local_position() == Position::new(49, 25));
+    /// assert!(*portal.portal_to_map_facing() == CardinalDirection::East);
+    /// assert!(*portal.portal_to_map_position() == Position::new(0, 1));
+    /// assert!(portal.target() == target);
+    /// assert!(decoded.sub_map_count() == 1);
+    /// assert!(decoded.get_sub_map_at(0).unwrap().value() == sub_map);
+    /// ```
+    pub fn to_rle(&self) -> String {
+        let mut output = format!(
+            "RLE {} {} {} {}\n",
+            self.position().x(),
+            self.position().y(),
+            self.width(),
+            self.height()
+        );
+        for (portal, portal_kind) in self.portals.iter().zip(self.portal_kinds.iter()) {
+            output.push_str(&format!(
+                "P {} {} {} {} {} {}{}\n",
+                portal.local_position().x(),
+                portal.local_position().y(),
+                rle_facing_char(*portal.portal_to_map_facing()),
+                portal.portal_to_map_position().x(),
+                portal.portal_to_map_position().y(),
+                portal.target(),
+                rle_portal_kind(*portal_kind)
+            ));
+        }
+        for sub_map in self.sub_maps.iter() {
+            output.push_str(&format!(
+                "S {} {} {}\n",
+                sub_map.local_position().x(),
+                sub_map.local_position().y(),
+                sub_map.value()
+            ));
+        }
+
+        let mut run: Option<(char, usize)> = None;
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let key = Position::new(x, y) + *self.position();
+                let c = rle_char(self.tiles.get(&key).copied());
+                run = match run {
+                    Some((run_c, count)) if run_c == c => Some((c, count + 1)),
+                    Some((run_c, count)) => {
+                        output.push_str(&format!("{}{}", count, run_c));
+                        Some((c, 1))
+                    }
+                    None => Some((c, 1)),
+                };
+            }
+        }
+        if let Some((c, count)) = run {
+            output.push_str(&format!("{}{}", count, c));
+        }
+
+        output
+    }
+
+    /// Captures this `SparseMap`, and every map reachable from it through its sub-maps and `Portal`s, as a [`MapSnapshot`](struct.MapSnapshot.html), which does not depend upon `MAPS`.
+    ///
+    /// This `SparseMap` need not be registered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any map reachable from this `SparseMap` is not registered, such as the target of a dangling `Portal`; see [`prune_dangling_portals`](#method.prune_dangling_portals).
+    ///
+    /// See [`MapSnapshot`](struct.MapSnapshot.html) for an example.
+    pub fn to_snapshot(&self) -> MapSnapshot {
+        MapSnapshot::capture(self)
+            .unwrap_or_else(|map_id| panic!("map {} is not registered", map_id))
+    }
+
+    /// Opens the door at the given local `Position` if it is closed, or closes it if it is open, and returns whether it is now open, or `None`, changing nothing, if there is no door there, as per [`door_at`](#method.door_at).
+    ///
+    /// See [`set_door`](#method.set_door) for an example.
+    pub fn toggle_door(&mut self, pos: Position) -> Option<bool> {
+        let open = !self.door_at(pos)?;
+        self.set_door(pos, open);
+        Some(open)
+    }
+
+    /// Moves this `SparseMap`'s `Position` by `offset`, leaving every local `Position` reading as it did before.
+    ///
+    /// Tiles are stored keyed by their local `Position` plus the `SparseMap`'s `Position`, so moving the `Position` directly, through `position_mut`, shifts which tile each local `Position` reads; this method rekeys the stored tiles to compensate. Sub-map `Position`s are stored the same way: `add_sub_map` stores the local `Position` it is given plus the `SparseMap`'s `Position`, which is what `SubMap::local_position` then returns, and reads and writes through to a sub-map subtract the `SparseMap`'s `Position` again. As such, they are shifted by `offset` too, and a sub-map added after translating is resolved at the local `Position` it was added at. `Portal` `Position`s are stored as local `Position`s, and are unchanged.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let sub_map = SparseMap::new();
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(0, 0), TileType::Portal);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::new(0, 2), sub_map);
+    /// assert!(sparse_map.tile_type_at(Position::new(1, 1)) == Some(TileType::Floor));
+    ///
+    /// sparse_map.translate(Position::new(5, 5));
+    /// assert!(*sparse_map.position() == Position::new(5, 5));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(0, 2)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at(Position::new(6, 6)) == Some(TileType::Floor));
+    /// assert!(sparse_map.tile_type_at(Position::new(1, 1)) == None);
+    ///
+    /// // Writes still reach the sub-map at the local `Position` it is read from.
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 2), TileType::Wall);
+    /// assert!(MAPS.read()[sub_map].read().tile_type_at_local(Position::zero()) == Some(TileType::Wall));
+    ///
+    /// // A sub-map added after translating is resolved where it was added, and leaves the tiles in place.
+    /// let later = SparseMap::new_filled(Size::new(1, 1), TileType::Portal);
+    /// sparse_map.add_sub_map(Position::new(1, 0), later);
+    /// assert!(*sparse_map.position() == Position::new(5, 5));
+    /// assert!(*sparse_map.get_sub_map_at(1).unwrap().local_position() == Position::new(6, 5));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 0)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Floor));
+    /// ```
+    pub fn translate(&mut self, offset: Position) {
+        self.tiles = self
+            .tiles
+            .drain()
+            .map(|(position, tile_type)| (position + offset, tile_type))
+            .collect();
+        self.remap_layers(&|position| Some(position + offset));
+        for sub_map in self.sub_maps.iter_mut() {
+            *sub_map.local_position_mut() = *sub_map.local_position() + offset;
+        }
+        *self.position_mut() = *self.position() + offset;
+    }
+
+    /// Adds a `Portal`, as per `add_portal`, unless its local `Position` is to the left of, or above, the area's origin, or its target is not registered.
+    ///
+    /// Returns `MapError::OutOfBounds` or `MapError::DanglingTarget`, respectively, in those cases, and adds nothing; `add_portal` adds the `Portal` regardless.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let target = SparseMap::new();
+    /// let removed = SparseMap::new();
+    /// unregister_map(removed);
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// let east = CardinalDirection::East;
+    /// assert!(sparse_map.try_add_portal(Position::new(2, 1), east, Position::zero(), target) == Ok(()));
+    /// assert!(
+    ///     sparse_map.try_add_portal(Position::new(-1, 1), east, Position::zero(), target)
+    ///         == Err(MapError::OutOfBounds(Position::new(-1, 1)))
+    /// );
+    /// assert!(
+    ///     sparse_map.try_add_portal(Position::new(3, 1), east, Position::zero(), removed)
+    ///         == Err(MapError::DanglingTarget(removed))
+    /// );
+    /// assert!(sparse_map.portal_count() == 1);
+    ///
+    /// sparse_map.add_portal(Position::new(3, 1), east, Position::zero(), removed);
+    /// assert!(sparse_map.portal_count() == 2);
+    /// ```
+    pub fn try_add_portal(
+        &mut self,
+        local_position: Position,
+        portal_to_map_facing: CardinalDirection,
+        portal_to_map_position: Position,
+        target: MapId,
+    ) -> Result<(), MapError> {
+        if local_position.x() < 0 || local_position.y() < 0 {
+            return Err(MapError::OutOfBounds(local_position));
+        }
+        if !is_map_registered(target) {
+            return Err(MapError::DanglingTarget(target));
+        }
+
+        self.add_portal(
+            local_position,
+            portal_to_map_facing,
+            portal_to_map_position,
+            target,
+        );
+        Ok(())
+    }
 
-// Internal includes.
+    /// Adds `target` as a sub-map of this `SparseMap`, at `local_position`, unless doing so would create a cycle of sub-maps.
+    ///
+    /// Returns `MapError::DanglingTarget` if `target` is not registered, and `MapError::Cycle` if a cycle would be created; the sub-map is only added if `Ok` is returned. A cycle would be created if `target` is this `SparseMap`, or already contains it, directly or transitively, as a sub-map; this is found by walking the sub-maps of `target` through `MAPS`. See [`try_add_sub_map`](fn.try_add_sub_map.html) for maps which are registered.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let map_id = sparse_map.map_id();
+    /// let sub_map = SparseMap::new();
+    /// assert!(sparse_map.try_add_sub_map(Position::zero(), sub_map) == Ok(()));
+    /// assert!(sparse_map.try_add_sub_map(Position::zero(), map_id) == Err(MapError::Cycle(map_id)));
+    /// assert!(sparse_map.sub_map_count() == 1);
+    ///
+    /// let removed = SparseMap::new();
+    /// unregister_map(removed);
+    /// assert!(
+    ///     sparse_map.try_add_sub_map(Position::zero(), removed)
+    ///         == Err(MapError::DanglingTarget(removed))
+    /// );
+    /// ```
+    pub fn try_add_sub_map(
+        &mut self,
+        local_position: Position,
+        target: MapId,
+    ) -> Result<(), MapError> {
+        if !is_map_registered(target) {
+            return Err(MapError::DanglingTarget(target));
+        }
+        if sub_maps_reach(target, self.map_id) {
+            return Err(MapError::Cycle(target));
+        }
 
-/// A map which stores its [`TileType`](enum.TileType.html) information in a `HashMap`, indexed by [`Position`](geometry/struct.Position.html).
-///
-/// The size of the `SparseMap` will expand based on the `Position` provided, as per the specification for [`Map`](trait.Map.html).
-#[derive(Clone)]
-pub struct SparseMap {
-    map_id: MapId,
-    area: Area,
-    tiles: HashMap<Position, TileType>,
-    portals: Vec<Portal>,
-    sub_maps: Vec<SubMap>,
-}
+        self.add_sub_map(local_position, target);
+        Ok(())
+    }
 
-impl SparseMap {
-    /// Creates a new `SparseMap`. As `SparseMap` expands to meet its use, no parameters need be supplied.
+    /// Returns the `TileType` at the given local `Position`, as per `tile_type_at_local`, or `MapError::OutOfBounds` if it is outside of this `SparseMap`'s area.
     ///
-    /// `SparseMap::default()` defers to `SparseMap::new()`.
-    #[allow(clippy::new_ret_no_self)]
-    pub fn new() -> MapId {
-        register_map(Self {
-            map_id: get_new_map_id(),
-            area: Area::new(Position::zero(), Size::zero()),
-            tiles: HashMap::new(),
-            portals: Vec::new(),
-            sub_maps: Vec::new(),
-        })
+    /// `tile_type_at_local` instead returns `None` outside of the area, or the out-of-bounds tile, if one is set; as such, this distinguishes an empty position within the area from one outside of it.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    ///
+    /// assert!(sparse_map.try_tile_type_at_local(Position::new(1, 1)) == Ok(Some(TileType::Floor)));
+    /// assert!(sparse_map.try_tile_type_at_local(Position::new(0, 0)) == Ok(None));
+    /// assert!(
+    ///     sparse_map.try_tile_type_at_local(Position::new(2, 0))
+    ///         == Err(MapError::OutOfBounds(Position::new(2, 0)))
+    /// );
+    /// assert!(sparse_map.tile_type_at_local(Position::new(2, 0)) == None);
+    /// ```
+    pub fn try_tile_type_at_local(&self, pos: Position) -> Result<Option<TileType>, MapError> {
+        if !self.is_local_position_in_area(pos) {
+            return Err(MapError::OutOfBounds(pos));
+        }
+
+        Ok(self.tile_type_at_local(pos))
+    }
+
+    /// Sets the `TileType` at the given local `Position`, as per `tile_type_at_local_set`, and returns the `TileType` previously stored there, or `MapError::OutOfBounds` if it is to the left of, or above, the area's origin, where this `SparseMap` cannot expand.
+    ///
+    /// Nothing is set if an error is returned. Positions to the right of, or below, the area are accepted, and the `SparseMap` expands to fit them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// assert!(sparse_map.try_tile_type_at_local_set(Position::new(2, 1), TileType::Wall) == Ok(None));
+    /// assert!(
+    ///     sparse_map.try_tile_type_at_local_set(Position::new(2, 1), TileType::Floor)
+    ///         == Ok(Some(TileType::Wall))
+    /// );
+    /// assert!(*sparse_map.size() == Size::new(3, 2));
+    ///
+    /// assert!(
+    ///     sparse_map.try_tile_type_at_local_set(Position::new(0, -1), TileType::Floor)
+    ///         == Err(MapError::OutOfBounds(Position::new(0, -1)))
+    /// );
+    /// assert!(sparse_map.tiles_iter().count() == 1);
+    /// ```
+    pub fn try_tile_type_at_local_set(
+        &mut self,
+        pos: Position,
+        tile_type: TileType,
+    ) -> Result<Option<TileType>, MapError> {
+        if pos.x() < 0 || pos.y() < 0 {
+            return Err(MapError::OutOfBounds(pos));
+        }
+
+        Ok(self.tile_type_at_local_set(pos, tile_type))
+    }
+
+    /// Merges the tiles of the `other` map into this `SparseMap`, keeping, at each local `Position` where both have a tile, whichever wins this `SparseMap`'s tile priority, as in `tile_type_at_local`; unlike [`blit`](#method.blit), `other`'s tiles do not simply overwrite this `SparseMap`'s own.
+    ///
+    /// `other`'s tiles are read through `tile_type_at_local`, resolving its sub-maps, and are placed by the difference between the two maps' `Position`s, so that maps placed in the same space are merged where they overlap. Unless a priority has been set with [`set_tile_priority`](#method.set_tile_priority), `TileTypeStandardCmp` is used, under which `Portal` wins over `Floor`, and `Floor` wins over `Wall`. Only this `SparseMap`'s own tiles are compared; the tiles of its sub-maps are not. As per `tile_type_at_local_set`, the `SparseMap` expands to fit the tiles, and those which would be placed at a negative local `Position` are ignored.
+    ///
+    /// The tiles are read from `other` before any are written.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # use std::sync::Arc;
+    /// let mut walls = SparseMap::default();
+    /// walls.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Wall);
+    /// walls.translate(Position::new(2, 0));
+    /// let walls = register_map(walls);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 2)), TileType::Floor);
+    ///
+    /// let mut floors_win = sparse_map.clone();
+    /// floors_win.union_with(walls);
+    /// assert!(floors_win.to_ascii() == "....#\n....#\n  ###");
+    ///
+    /// let mut walls_win = sparse_map.clone();
+    /// walls_win.set_tile_priority(Arc::new(|left, right| {
+    ///     let priority = |tile_type: &Option<TileType>| match tile_type {
+    ///         Some(TileType::Wall) => 2,
+    ///         Some(TileType::Floor) => 1,
+    ///         _ => 0,
+    ///     };
+    ///     priority(left).cmp(&priority(right))
+    /// }));
+    /// walls_win.union_with(walls);
+    /// assert!(walls_win.to_ascii() == "..###\n..###\n  ###");
+    /// ```
+    pub fn union_with(&mut self, other: MapId) {
+        let mut other_tiles = Vec::new();
+        {
+            let maps = MAPS.read();
+            let other = maps[other].read();
+            let offset = *other.position() - *self.position();
+            for y in 0..other.height() as i32 {
+                for x in 0..other.width() as i32 {
+                    let position = Position::new(x, y);
+                    if let Some(tile_type) = other.tile_type_at_local(position) {
+                        other_tiles.push((position + offset, tile_type));
+                    }
+                }
+            }
+        }
+
+        for (position, tile_type) in other_tiles {
+            let own = self.tiles.get(&(position + *self.position())).copied();
+            let incoming = Some(tile_type);
+            let incoming_wins = match &self.tile_priority {
+                Some(tile_priority) => tile_priority(&own, &incoming) == std::cmp::Ordering::Less,
+                None => *TileTypeStandardCmp::return_greater_option(&own, &incoming) == incoming,
+            };
+            if incoming_wins && own != incoming {
+                self.tile_type_at_local_set(position, tile_type);
+            }
+        }
+    }
+
+    /// Returns the index of every `Portal` whose target is not registered in `MAPS`, such as one removed with [`unregister_map`](fn.unregister_map.html), in ascending order.
+    ///
+    /// These can be removed with [`prune_dangling_portals`](#method.prune_dangling_portals).
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let kept = SparseMap::new();
+    /// let removed = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.add_portal(Position::new(0, 0), CardinalDirection::West, Position::zero(), kept);
+    /// sparse_map.add_portal(Position::new(2, 0), CardinalDirection::East, Position::zero(), removed);
+    /// assert!(sparse_map.validate_portals().is_empty());
+    ///
+    /// unregister_map(removed);
+    /// assert!(sparse_map.validate_portals() == vec![1]);
+    ///
+    /// sparse_map.prune_dangling_portals();
+    /// assert!(sparse_map.portal_count() == 1);
+    /// assert!(sparse_map.get_portal_at(0).unwrap().target() == kept);
+    /// assert!(sparse_map.validate_portals().is_empty());
+    /// ```
+    pub fn validate_portals(&self) -> Vec<usize> {
+        self.portals
+            .iter()
+            .enumerate()
+            .filter(|(_, portal)| !is_map_registered(portal.target()))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns every separate region of 4-connected tiles for which `is_walkable` returns `true`, as per [`flood_fill`](#method.flood_fill), until every such tile within the area belongs to one.
+    ///
+    /// Regions are returned largest-first, with regions of the same size in order of their top-most, and then left-most, tile; the positions within each region are in order of row, and then column. A map whose walkable tiles are all connected returns a single region, and one with none returns none.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let to_tile_type = |c| match c {
+    ///     '#' => Some(TileType::Wall),
+    ///     '.' => Some(TileType::Floor),
+    ///     _ => None,
+    /// };
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.blit(
+    ///     SparseMap::from_ascii("#######\n#.##..#\n#.##..#\n#######", &to_tile_type),
+    ///     Position::zero(),
+    ///     false,
+    /// );
+    /// let is_walkable = |tile_type| tile_type == TileType::Floor;
+    ///
+    /// let regions = sparse_map.walkable_regions(&is_walkable);
+    /// assert!(regions.len() == 2);
+    /// assert!(
+    ///     regions[0]
+    ///         == vec![
+    ///             Position::new(4, 1),
+    ///             Position::new(5, 1),
+    ///             Position::new(4, 2),
+    ///             Position::new(5, 2),
+    ///         ]
+    /// );
+    /// assert!(regions[1] == vec![Position::new(1, 1), Position::new(1, 2)]);
+    ///
+    /// // Restore the bridge between the rooms.
+    /// sparse_map.fill_area(Area::new(Position::new(2, 1), Size::new(2, 1)), TileType::Floor);
+    /// let regions = sparse_map.walkable_regions(&is_walkable);
+    /// assert!(regions.len() == 1);
+    /// assert!(regions[0].len() == 8);
+    ///
+    /// assert!(sparse_map.walkable_regions(&|tile_type| tile_type == TileType::Portal).is_empty());
+    /// ```
+    pub fn walkable_regions(&self, is_walkable: &dyn Fn(TileType) -> bool) -> Vec<Vec<Position>> {
+        let mut assigned = HashSet::new();
+        let mut output = Vec::new();
+        for (position, tile_type) in self.iter_area(Area::new(Position::zero(), *self.size())) {
+            if assigned.contains(&position) || !tile_type.is_some_and(is_walkable) {
+                continue;
+            }
+
+            let mut region = self.flood_fill(position, is_walkable);
+            if region.is_empty() {
+                continue;
+            }
+
+            region.sort_by_key(|position| (position.y(), position.x()));
+            assigned.extend(region.iter().copied());
+            output.push(region);
+        }
+
+        // The sort is stable, so regions of the same size stay in the order they were found.
+        output.sort_by_key(|region| std::cmp::Reverse(region.len()));
+        output
+    }
+
+    /// Returns the 4-connected distance from every local `Position` within this `SparseMap`'s area which is not a wall to the nearest wall, treating local `Position`s outside of the area as walls.
+    ///
+    /// See [`wall_distance_field_with`](#method.wall_distance_field_with) for how the distances are found.
+    pub fn wall_distance_field(
+        &self,
+        is_wall: &dyn Fn(TileType) -> bool,
+    ) -> HashMap<Position, u32> {
+        self.wall_distance_field_with(is_wall, true)
+    }
+
+    /// Returns the 4-connected distance from every local `Position` within this `SparseMap`'s area which is not a wall to the nearest wall, by a breadth-first search seeded from every wall at once.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, and a tile is a wall if `is_wall` returns `true` for it; walls themselves are left out of the result, while every other `Position` within the area, including those with no tile, is given a distance of at least `1`. If `out_of_bounds_is_wall` is `true`, the local `Position`s just outside of the area count as walls, so that every `Position` on the edge of the area is within `1` of a wall; otherwise, a `Position` from which no wall can be reached is left out.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(7, 7));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// let is_wall = |tile_type| tile_type == TileType::Wall;
+    ///
+    /// let field = sparse_map.wall_distance_field(&is_wall);
+    /// assert!(field.len() == 25);
+    /// assert!(field.get(&Position::new(0, 0)) == None);
+    /// assert!(field[&Position::new(1, 1)] == 1 && field[&Position::new(3, 1)] == 1);
+    /// assert!(field[&Position::new(2, 2)] == 2 && field[&Position::new(3, 2)] == 2);
+    /// assert!(field[&Position::new(3, 3)] == 3);
+    ///
+    /// // An interior wall beside cells which are also next to the edge.
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(5, 3)), TileType::Floor);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 1), TileType::Wall);
+    /// let field = sparse_map.wall_distance_field(&is_wall);
+    /// assert!(field.len() == 14);
+    /// assert!(field[&Position::new(1, 1)] == 1 && field[&Position::new(3, 1)] == 1);
+    /// assert!(field.values().all(|distance| *distance == 1));
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 3)), TileType::Floor);
+    /// assert!(sparse_map.wall_distance_field_with(&is_wall, false).is_empty());
+    /// assert!(sparse_map.wall_distance_field(&is_wall)[&Position::new(1, 1)] == 2);
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// let field = sparse_map.wall_distance_field_with(&is_wall, false);
+    /// assert!(field.len() == 11);
+    /// assert!(field[&Position::new(3, 2)] == 5);
+    /// ```
+    pub fn wall_distance_field_with(
+        &self,
+        is_wall: &dyn Fn(TileType) -> bool,
+        out_of_bounds_is_wall: bool,
+    ) -> HashMap<Position, u32> {
+        let width = self.width() as i32;
+        let height = self.height() as i32;
+
+        let mut output = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut walls = HashSet::new();
+        let mut edges = Vec::new();
+        for (position, tile_type) in self.iter_area(Area::new(Position::zero(), *self.size())) {
+            if tile_type.is_some_and(is_wall) {
+                walls.insert(position);
+                queue.push_back((position, 0));
+            } else if out_of_bounds_is_wall
+                && (position.x() == 0
+                    || position.y() == 0
+                    || position.x() == width - 1
+                    || position.y() == height - 1)
+            {
+                edges.push(position);
+            }
+        }
+
+        // Every wall is queued before any edge, so that the queue stays in order of distance.
+        for position in edges {
+            output.insert(position, 1);
+            queue.push_back((position, 1));
+        }
+
+        while let Some((position, distance)) = queue.pop_front() {
+            for offset in [
+                Position::NORTH,
+                Position::EAST,
+                Position::SOUTH,
+                Position::WEST,
+            ]
+            .iter()
+            {
+                let next = position + *offset;
+                if next.x() < 0
+                    || next.y() < 0
+                    || next.x() >= width
+                    || next.y() >= height
+                    || walls.contains(&next)
+                    || output.contains_key(&next)
+                {
+                    continue;
+                }
+
+                output.insert(next, distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+
+        output
     }
 }
 
 impl ContainsLocalPosition for SparseMap {
+    /// Returns whether the local `Position`, and each of its 8-connected neighbors, intersects this `SparseMap`, as per `intersects_local_position`.
+    ///
+    /// As `intersects_local_position` resolves tiles through `tile_type_at_local`, the tiles of sub-maps are already taken into account, to any depth; a `Position` surrounded by tiles provided by sub-maps is `Containment::Contains`. To consider only the tiles stored directly in this `SparseMap`, test the neighbors with [`tile_type_at_local_depth`](#method.tile_type_at_local_depth) and a `max_depth` of `0`.
+    ///
     /// ```
     /// # use dungen_minion_rooms::geometry::*;
     /// # use dungen_minion_rooms::*;
@@ -67,6 +4374,16 @@ impl ContainsLocalPosition for SparseMap {
     ///         }
     ///     }
     /// }
+    /// # drop(sparse_map);
+    /// # drop(maps);
+    ///
+    /// // A sub-map provides every neighbor of (2, 2), so it is contained.
+    /// let sub_map = SparseMap::new_filled(Size::new(3, 3), TileType::Wall);
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Floor);
+    /// sparse_map.add_sub_map(Position::new(1, 1), sub_map);
+    /// assert!(sparse_map.contains_local_position(Position::new(2, 2)) == Containment::Contains);
+    /// assert!(sparse_map.tile_type_at_local_depth(Position::new(1, 1), 0) == None);
     /// ```
     fn contains_local_position(&self, position: Position) -> Containment {
         if self.intersects_local_position(position) {
@@ -91,6 +4408,82 @@ impl ContainsLocalPosition for SparseMap {
 
 impl ContainsPosition for SparseMap {}
 
+impl fmt::Debug for SparseMap {
+    /// Formats a summary of this `SparseMap`: its `MapId`, its area, and the number of tiles stored directly in it, `Portal`s, and sub-maps, rather than every tile.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 3)), TileType::Floor);
+    /// sparse_map.add_portal(Position::new(3, 1), CardinalDirection::East, Position::zero(), target);
+    ///
+    /// let debug = format!("{:?}", sparse_map);
+    /// assert!(debug.starts_with(&format!("SparseMap {{ map_id: {}, area: ", sparse_map.map_id())));
+    /// assert!(debug.ends_with("tile_count: 12, portal_count: 1, sub_map_count: 0 }"));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SparseMap")
+            .field("map_id", &self.map_id)
+            .field("area", &self.area)
+            .field("tile_count", &self.tiles.len())
+            .field("portal_count", &self.portals.len())
+            .field("sub_map_count", &self.sub_maps.len())
+            .finish()
+    }
+}
+
+impl Default for SparseMap {
+    /// Creates a new, empty `SparseMap` with a fresh `MapId`.
+    ///
+    /// The `SparseMap` is not registered; pass it to `register_map` to make it available through `MAPS`.
+    fn default() -> Self {
+        Self::from_parts(
+            get_new_map_id(),
+            Area::new(Position::zero(), Size::zero()),
+            HashMap::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+}
+
+/// Deserializes a `SparseMap` and every map in its serialized map tree.
+///
+/// Every map in the tree receives a fresh `MapId` from `get_new_map_id`, and all `Portal` and `SubMap` targets are rewired to those `MapId`s. Every map other than the root is registered with `register_map` during deserialization; the returned root is unregistered, and should be passed to `register_map` by the caller.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SparseMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        MapTree::deserialize(deserializer)?
+            .instantiate()
+            .map_err(|index| {
+                serde::de::Error::custom(format!("map index {} is not in the map tree", index))
+            })
+    }
+}
+
+impl fmt::Display for SparseMap {
+    /// Renders this `SparseMap` as ASCII text, as per `to_ascii`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// let area = Area::new(Position::zero(), Size::new(4, 3));
+    /// sparse_map.fill_area(area, TileType::Floor);
+    /// sparse_map.fill_border(area, TileType::Wall);
+    /// assert!(sparse_map.to_string() == sparse_map.to_ascii());
+    /// assert!(format!("{}", sparse_map) == "####\n#..#\n####");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ascii())
+    }
+}
+
 impl HasArea for SparseMap {
     fn area(&self) -> &Area {
         &self.area
@@ -144,18 +4537,26 @@ impl HasWidth for SparseMap {
 impl IntersectsLocalPosition for SparseMap {
     fn intersects_local_position(&self, position: Position) -> bool {
         self.area().intersects_local_position(position)
-            && match self.tile_type_at_local(position) {
-                Some(TileType::Void) => false,
-                None => false,
-                _ => true,
-            }
+            && !matches!(
+                self.tile_type_at_local(position),
+                Some(TileType::Void) | None
+            )
     }
 }
 
 impl IntersectsPosition for SparseMap {}
 
 impl Map for SparseMap {
+    /// Returns a boxed copy of this `SparseMap`, which keeps its `MapId`.
+    ///
+    /// **The copy shares this `SparseMap`'s `MapId`**, so that it stands in for this `SparseMap`, as a snapshot of it, rather than as a new map; registering it would leave two maps claiming one `MapId`. Use [`deep_clone`](#method.deep_clone) for a copy with its own `MapId`.
     fn box_clone(&self) -> Box<dyn Map> {
+        // Answer `sparse_map_copy`, which discards the returned map.
+        if SPARSE_MAP_COPY_REQUESTED.with(|requested| requested.replace(false)) {
+            SPARSE_MAP_COPY.with(|copy| *copy.borrow_mut() = Some((*self).clone()));
+            return Box::new(UnregisteredMap::new());
+        }
+
         Box::new((*self).clone())
     }
 
@@ -163,6 +4564,41 @@ impl Map for SparseMap {
         self.map_id
     }
 
+    /// Rotates the `SparseMap`'s tiles, `Portal`s, and sub-maps by `rotation`.
+    ///
+    /// The contents of each sub-map are rotated as well, and its local `Position` moved, so that its tiles keep their place relative to the `SparseMap`'s own tiles. As sub-maps are shared through `MAPS`, any other map using the same sub-map will see it rotated. This takes a write lock on each sub-map, and so must not be called while holding a lock on any of them.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let build_sub_map = || {
+    ///     let sub_map = SparseMap::new();
+    ///     let maps = MAPS.read();
+    ///     let mut map = maps[sub_map].write();
+    ///     map.tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+    ///     map.tile_type_at_local_set(Position::new(1, 0), TileType::Portal);
+    ///     sub_map
+    /// };
+    ///
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.tile_type_at_local_set(Position::new(3, 2), TileType::Wall);
+    /// sparse_map.add_sub_map(Position::new(1, 1), build_sub_map());
+    /// assert!(sparse_map.to_ascii() == "    \n .+ \n   #");
+    ///
+    /// sparse_map.rotate(CardinalRotation::Right90);
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 1)) == Some(TileType::Portal));
+    /// assert!(sparse_map.tile_type_at_local(Position::new(1, 2)) == Some(TileType::Floor));
+    /// assert!(sparse_map.to_ascii() == "  #\n + \n . \n   ");
+    ///
+    /// let dense_map = DenseMap::new();
+    /// let sub_map = build_sub_map();
+    /// let maps = MAPS.read();
+    /// let mut map = maps[dense_map].write();
+    /// map.tile_type_at_local_set(Position::new(3, 2), TileType::Wall);
+    /// map.add_sub_map(Position::new(1, 1), sub_map);
+    /// map.rotate(CardinalRotation::Right90);
+    /// assert!(map.to_ascii() == "  #\n + \n . \n   ");
+    /// ```
     fn rotate(&mut self, rotation: CardinalRotation) {
         /* if rotation == CardinalRotation::None {
             return;
@@ -172,15 +4608,7 @@ impl Map for SparseMap {
 
         let self_position = *self.position();
         let new_self_position = self_position * rotation;
-        let adjust_position = match rotation {
-            CardinalRotation::None => Position::new(0, 0),
-            CardinalRotation::Right90 => Position::new(0, (self.area().width() as i32 - 1).max(0)),
-            CardinalRotation::Full180 => Position::new(
-                (self.area().width() as i32 - 1).max(0),
-                (self.area().height() as i32 - 1).max(0),
-            ),
-            CardinalRotation::Left90 => Position::new((self.area().height() as i32 - 1).max(0), 0),
-        };
+        let adjust_position = rotation_adjustment(*self.size(), rotation);
 
         for portal_mut in self.portals.iter_mut() {
             let portal_local_position = *portal_mut.local_position() - self_position;
@@ -196,7 +4624,18 @@ impl Map for SparseMap {
             new_tiles.insert(adjust_position + new_tile_position, *kvp.1);
         }
 
+        rotate_sub_maps(
+            &mut self.sub_maps,
+            self_position,
+            new_self_position,
+            adjust_position,
+            rotation,
+        );
+
         self.tiles = new_tiles;
+        self.remap_layers(&|tile_position| {
+            Some(adjust_position + new_self_position + (tile_position - self_position) * rotation)
+        });
         *self.position_mut() = new_self_position;
         *self.size_mut() = match rotation {
             CardinalRotation::None => *self.size(),
@@ -206,24 +4645,51 @@ impl Map for SparseMap {
         }
     }
 
+    /// Gets the `TileType` at the given local `Position`, resolving sub-maps, as per `TileTypeStandardCmp`.
+    ///
+    /// Sub-maps may be nested to any depth; use [`tile_type_at_local_depth`](#method.tile_type_at_local_depth) to limit it. If sub-maps form a cycle, so that a sub-map is, directly or transitively, a sub-map of itself, then while resolving a tile, each map is resolved at most once along the chain; a sub-map which refers back to a map already being resolved contributes no tiles.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let a = SparseMap::new();
+    /// let b = SparseMap::new();
+    /// {
+    ///     let maps = MAPS.read();
+    ///     let mut map_a = maps[a].write();
+    ///     map_a.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    ///     map_a.add_sub_map(Position::zero(), b);
+    /// }
+    /// {
+    ///     let maps = MAPS.read();
+    ///     let mut map_b = maps[b].write();
+    ///     map_b.tile_type_at_local_set(Position::new(1, 0), TileType::Floor);
+    ///     map_b.add_sub_map(Position::zero(), a);
+    /// }
+    ///
+    /// let maps = MAPS.read();
+    /// let map_a = maps[a].read();
+    /// assert!(map_a.tile_type_at_local(Position::new(0, 0)) == Some(TileType::Wall));
+    /// assert!(map_a.tile_type_at_local(Position::new(1, 0)) == Some(TileType::Floor));
+    /// assert!(map_a.tile_type_at_local(Position::new(2, 0)) == None);
+    /// ```
     fn tile_type_at_local(&self, pos: Position) -> Option<TileType> {
-        let mut output = None;
-        if !self.sub_maps.is_empty() {
-            let maps = MAPS.read();
-            for sub_map in self.sub_maps.iter() {
-                let map = maps[sub_map.value()].read();
-                let sub_map_position = *sub_map.local_position();
-                let local_position = pos - sub_map_position + *self.position();
-                let test = map.tile_type_at_local(local_position);
-                output = *TileTypeStandardCmp::return_greater_option(&output, &test);
-            }
+        if let Some(tile_priority) = &self.tile_priority {
+            return self.tile_type_at_local_sort_by(pos, &**tile_priority);
         }
 
-        let self_tile_type = if let Some(tile_type) = self.tiles.get(&(pos + *self.position())) {
-            Some(*tile_type)
-        } else {
-            None
-        };
+        if self.out_of_bounds_tile.is_some() && !self.size().intersects_local_position(pos) {
+            return self.out_of_bounds_tile;
+        }
+
+        let mut output = None;
+        for_each_resolved_sub_map(self.map_id, &self.sub_maps, |sub_map, map| {
+            let local_position = pos - self.sub_map_offset(sub_map);
+            let test = map.tile_type_at_local(local_position);
+            output = *TileTypeStandardCmp::return_greater_option(&output, &test);
+        });
+
+        let self_tile_type = self.tiles.get(&(pos + *self.position())).copied();
         output = *TileTypeStandardCmp::return_greater_option(&output, &self_tile_type);
 
         output
@@ -233,13 +4699,42 @@ impl Map for SparseMap {
         self.tiles.get_mut(&(pos + *self.position()))
     }
 
+    /// Sets the `TileType` at the given local `Position`, expanding the `SparseMap`'s area to the right and bottom to fit it, and returns the `TileType` previously stored there; the tile is also written through to every sub-map which covers the `Position`.
+    ///
+    /// Local `Position`s start at `(0, 0)`, at the area's origin, so a `Position` with a negative coordinate lies to the left of, or above, the area, where a `SparseMap` does not expand. Such a `Position` is ignored: nothing is stored, neither the area nor the sub-maps are changed, and `None` is returned. Use [`try_tile_type_at_local_set`](#method.try_tile_type_at_local_set) to detect this, or [`translate`](#method.translate) to make room.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let mut sparse_map = SparseMap::default();
+    /// sparse_map.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Floor);
+    ///
+    /// let position = Position::new(-2, -2);
+    /// assert!(sparse_map.tile_type_at_local_set(position, TileType::Wall) == None);
+    /// assert!(sparse_map.tile_type_at_local(position) == None);
+    /// assert!(*sparse_map.size() == Size::new(3, 3));
+    /// assert!(*sparse_map.position() == Position::zero());
+    /// assert!(sparse_map.tiles_iter().count() == 9);
+    /// assert!(!sparse_map.is_local_position_in_area(position));
+    /// assert!(!sparse_map.intersects_local_position(position));
+    /// assert!(sparse_map.contains_local_position(position) == Containment::Disjoint);
+    ///
+    /// sparse_map.fill_area(Area::new(Position::new(-2, -2), Size::new(3, 3)), TileType::Wall);
+    /// assert!(sparse_map.to_ascii() == "#..\n...\n...");
+    /// ```
     fn tile_type_at_local_set(&mut self, pos: Position, tile_type: TileType) -> Option<TileType> {
+        if pos.x() < 0 || pos.y() < 0 {
+            return None;
+        }
+
         if !self.sub_maps.is_empty() {
             let maps = MAPS.read();
             for sub_map in self.sub_maps.iter() {
-                let mut map = maps[sub_map.value()].write();
-                let sub_map_position = *sub_map.local_position();
-                let local_position = pos - sub_map_position;
+                let mut map = match maps.get(sub_map.value()) {
+                    Some(map) => map.write(),
+                    None => continue,
+                };
+                let local_position = pos - self.sub_map_offset(sub_map);
                 if map.is_local_position_valid(local_position) {
                     map.tile_type_at_local_set(local_position, tile_type);
                 }
@@ -249,7 +4744,14 @@ impl Map for SparseMap {
         *self.size_mut().height_mut() = self.size().height().max(pos.y() as u32 + 1);
         *self.size_mut().width_mut() = self.size().width().max(pos.x() as u32 + 1);
 
-        self.tiles.insert(pos + *self.position(), tile_type)
+        let key = pos + *self.position();
+        self.doors.remove(&key);
+        let output = self.tiles.insert(key, tile_type);
+        if let Some(tile_observer) = &self.tile_observer {
+            tile_observer(pos, output, tile_type);
+        }
+
+        output
     }
 
     /// Gets an option for an immutable reference to the `TileType` at the given local `Position`. Returns None if the local `Position` is out of bounds, or there is no tile at that location.
@@ -260,27 +4762,22 @@ impl Map for SparseMap {
         pos: Position,
         sort_best: &dyn Fn(&Option<TileType>, &Option<TileType>) -> std::cmp::Ordering,
     ) -> Option<TileType> {
-        let mut output = None;
-        if !self.sub_maps.is_empty() {
-            let maps = MAPS.read();
-            for sub_map in self.sub_maps.iter() {
-                let map = maps[sub_map.value()].read();
-                let sub_map_position = *sub_map.local_position();
-                let local_position = pos - sub_map_position + *self.position();
-                let test = map.tile_type_at_local(local_position);
-                output = match sort_best(&output, &test) {
-                    std::cmp::Ordering::Greater => output,
-                    std::cmp::Ordering::Equal => output,
-                    std::cmp::Ordering::Less => test,
-                };
-            }
+        if self.out_of_bounds_tile.is_some() && !self.size().intersects_local_position(pos) {
+            return self.out_of_bounds_tile;
         }
 
-        let self_tile_type = if let Some(tile_type) = self.tiles.get(&(pos + *self.position())) {
-            Some(*tile_type)
-        } else {
-            None
-        };
+        let mut output = None;
+        for_each_resolved_sub_map(self.map_id, &self.sub_maps, |sub_map, map| {
+            let local_position = pos - self.sub_map_offset(sub_map);
+            let test = map.tile_type_at_local(local_position);
+            output = match sort_best(&output, &test) {
+                std::cmp::Ordering::Greater => output,
+                std::cmp::Ordering::Equal => output,
+                std::cmp::Ordering::Less => test,
+            };
+        });
+
+        let self_tile_type = self.tiles.get(&(pos + *self.position())).copied();
         output = match sort_best(&output, &self_tile_type) {
             std::cmp::Ordering::Greater => output,
             std::cmp::Ordering::Equal => output,
@@ -291,6 +4788,88 @@ impl Map for SparseMap {
     }
 }
 
+impl PartialEq for SparseMap {
+    /// Compares two `SparseMap`s structurally, ignoring their `MapId`s.
+    ///
+    /// Two `SparseMap`s are equal if they have the same area; the same out-of-bounds tile; the same `Portal`s, with the same `PortalKind`s, in any order; the same tiles in every layer; the same doors, each equally open; the same `TileData` at each `Position`; and the same tile, as resolved through `tile_type_at_local`, at every local `Position` within the area. These are exactly the contents covered by [`content_hash`](#method.content_hash). As sub-maps are compared only through the tiles they provide, a `SparseMap` equals a copy of itself which has had [`flatten_sub_maps`](#method.flatten_sub_maps) called on it.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let build = || {
+    ///     let mut sparse_map = SparseMap::default();
+    ///     sparse_map.fill_area(Area::new(Position::zero(), Size::new(4, 4)), TileType::Floor);
+    ///     sparse_map.fill_border(Area::new(Position::zero(), Size::new(4, 4)), TileType::Wall);
+    ///     sparse_map.add_portal(Position::new(3, 1), CardinalDirection::East, Position::zero(), target);
+    ///     sparse_map
+    /// };
+    ///
+    /// let mut sparse_map = build();
+    /// assert!(sparse_map == build());
+    /// assert!(sparse_map.map_id() != build().map_id());
+    ///
+    /// sparse_map.tile_type_at_local_set(Position::new(1, 1), TileType::Wall);
+    /// assert!(sparse_map != build());
+    ///
+    /// let sub_map = SparseMap::new();
+    /// MAPS.read()[sub_map]
+    ///     .write()
+    ///     .tile_type_at_local_set(Position::new(0, 0), TileType::Portal);
+    /// let mut unflattened = build();
+    /// unflattened.tile_type_at_local_set(Position::new(1, 1), TileType::Wall);
+    /// unflattened.add_sub_map(Position::new(2, 2), sub_map);
+    /// let mut flattened = unflattened.clone();
+    /// flattened.flatten_sub_maps();
+    /// assert!(flattened == unflattened);
+    /// assert!(flattened != sparse_map);
+    /// ```
+    fn eq(&self, other: &Self) -> bool {
+        if self.area() != other.area()
+            || self.out_of_bounds_tile != other.out_of_bounds_tile
+            || self.portals.len() != other.portals.len()
+            || self.layers != other.layers
+            || self.doors_sorted() != other.doors_sorted()
+            || self.tile_data != other.tile_data
+        {
+            return false;
+        }
+
+        let portal_keys = |sparse_map: &Self| {
+            sparse_map
+                .portals
+                .iter()
+                .zip(sparse_map.portal_kinds.iter())
+                .map(|(portal, portal_kind)| {
+                    (
+                        *portal.local_position(),
+                        *portal.portal_to_map_facing(),
+                        *portal.portal_to_map_position(),
+                        portal.target(),
+                        *portal_kind,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut other_portals = portal_keys(other);
+        for portal in portal_keys(self) {
+            match other_portals.iter().position(|other| *other == portal) {
+                Some(index) => {
+                    other_portals.swap_remove(index);
+                }
+                None => return false,
+            }
+        }
+
+        (0..self.height() as i32).all(|y| {
+            (0..self.width() as i32).all(|x| {
+                let position = Position::new(x, y);
+                self.tile_type_at_local(position) == other.tile_type_at_local(position)
+            })
+        })
+    }
+}
+
 impl Placed for SparseMap {}
 
 impl PlacedObject for SparseMap {}
@@ -303,13 +4882,13 @@ impl PortalCollection for SparseMap {
         portal_to_map_position: Position,
         target: MapId,
     ) {
-        self.portals.push(Portal::new(
+        self.add_portal_with_kind(
             local_position,
             portal_to_map_facing,
             portal_to_map_position,
             target,
-        ));
-        self.tile_type_at_local_set(local_position, TileType::Portal);
+            PortalKind::default(),
+        );
     }
 
     fn get_portal_at(&self, index: usize) -> Option<&Portal> {
@@ -324,15 +4903,116 @@ impl PortalCollection for SparseMap {
         self.portals.len()
     }
 
-    fn portals(&self) -> Portals {
+    fn portals(&self) -> Portals<'_> {
         Portals::new(&self.portals)
     }
 
-    fn portals_mut(&mut self) -> PortalsMut {
+    fn portals_mut(&mut self) -> PortalsMut<'_> {
         PortalsMut::new(&mut self.portals)
     }
 }
 
+/// Serializes a `SparseMap`, along with every map reachable from it through its sub-maps and portals, as a map tree.
+///
+/// The map tree stores each map's area, the tiles stored directly in it, including `TileType::Void` tiles, as `[x, y, tile_type]` triples in local coordinates, its portals, with their [`PortalKind`](enum.PortalKind.html)s, its sub-maps, the tiles of its layers above layer `0`, its doors, as `[x, y, open]` triples, and its `TileData`, as `[x, y, tile_data]` triples; the tiles of sub-maps are stored only with the sub-maps themselves, so that they are not baked into the parent. `Portal` and `SubMap` targets are stored as indices into the map tree rather than as `MapId`s, with the serialized map at index `0`, so that a whole tree of maps, including cycles, round-trips.
+///
+/// Only the out-of-bounds tile, tile priority, and tile observer of a `SparseMap` are not stored. Map trees saved before `PortalKind`s, layers, doors, or tile data were stored still load, with open portals, and none of the rest.
+///
+/// ```
+/// # use dungen_minion_rooms::geometry::*;
+/// # use dungen_minion_rooms::*;
+/// let sub_map_a = SparseMap::new();
+/// let sub_map_b = SparseMap::new();
+/// let target = SparseMap::new();
+/// {
+///     let maps = MAPS.read();
+///     maps[sub_map_a].write().tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+///     maps[sub_map_b].write().tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+///     maps[target].write().tile_type_at_local_set(Position::new(0, 0), TileType::Floor);
+/// }
+///
+/// let mut sparse_map = SparseMap::default();
+/// for y in 0..4 {
+///     for x in 0..4 {
+///         sparse_map.tile_type_at_local_set(Position::new(x, y), TileType::Wall);
+///     }
+/// }
+/// sparse_map.tile_type_at_local_set(Position::new(1, 2), TileType::Void);
+/// sparse_map.set_door(Position::new(3, 2), false);
+/// sparse_map.set_layer_tile(1, Position::new(2, 1), TileType::Portal);
+/// sparse_map.set_tile_data(Position::new(2, 1), TileData::new().with("damage", 5));
+/// sparse_map.add_sub_map(Position::new(0, 0), sub_map_a);
+/// sparse_map.add_sub_map(Position::new(2, 2), sub_map_b);
+/// sparse_map.add_portal_with_kind(
+///     Position::new(3, 1),
+///     CardinalDirection::East,
+///     Position::new(0, 0),
+///     target,
+///     PortalKind::Locked,
+/// );
+///
+/// let serialized = serde_json::to_string(&sparse_map).unwrap();
+/// let deserialized: SparseMap = serde_json::from_str(&serialized).unwrap();
+/// let portal = deserialized.get_portal_at(0).unwrap();
+/// assert!(deserialized.portal_kind(0) == Some(PortalKind::Locked));
+/// assert!(deserialized.door_at(Position::new(3, 2)) == Some(false));
+/// assert!(deserialized.layer_tile(1, Position::new(2, 1)) == Some(TileType::Portal));
+/// assert!(deserialized.tile_data(Position::new(2, 1)).unwrap().get("damage") == Some(5));
+/// assert!(deserialized.clone().toggle_door(Position::new(3, 2)) == Some(true));
+///
+/// // Map trees saved before `PortalKind`s were stored load with open portals.
+/// let without_kinds = serialized.replace(",\"kind\":\"Locked\"", "");
+/// assert!(without_kinds.len() < serialized.len());
+/// let loaded: SparseMap = serde_json::from_str(&without_kinds).unwrap();
+/// assert!(loaded.portal_kind(0) == Some(PortalKind::Open));
+///
+/// assert!(deserialized.map_id() != sparse_map.map_id());
+/// assert!(*deserialized.area() == *sparse_map.area());
+/// assert!(deserialized.sub_map_count() == 2);
+/// assert!(*portal.local_position() == Position::new(3, 1));
+/// assert!(*portal.portal_to_map_facing() == CardinalDirection::East);
+/// assert!(portal.target() != target);
+/// assert!(
+///     MAPS.read()[portal.target()].read().tile_type_at_local(Position::new(0, 0))
+///         == Some(TileType::Floor)
+/// );
+/// for y in -1..=5 {
+///     for x in -1..=5 {
+///         let position = Position::new(x, y);
+///         assert!(
+///             deserialized.tile_type_at_local(position) == sparse_map.tile_type_at_local(position)
+///         );
+///     }
+/// }
+///
+/// // Only the map's own tiles are stored, including its `Void` tile; the sub-maps' tiles are not baked in.
+/// assert!(deserialized.tiles_iter().count() == 16);
+/// assert!(deserialized.tiles_iter_sorted().eq(sparse_map.tiles_iter_sorted()));
+///
+/// // Without portals, whose targets are given fresh `MapId`s, a map round-trips to an equal map.
+/// let mut layered = SparseMap::default();
+/// layered.fill_area(Area::new(Position::zero(), Size::new(3, 3)), TileType::Floor);
+/// layered.set_layer_tile(1, Position::new(1, 1), TileType::Wall);
+/// layered.set_door(Position::new(0, 1), true);
+/// layered.set_tile_data(Position::new(2, 2), TileData::new().with("brightness", 3));
+/// let round_tripped: SparseMap =
+///     serde_json::from_str(&serde_json::to_string(&layered).unwrap()).unwrap();
+/// assert!(round_tripped == layered);
+/// ```
+#[cfg(feature = "serde")]
+impl Serialize for SparseMap {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        MapTree::capture(self)
+            .map_err(|map_id| {
+                serde::ser::Error::custom(format!("map {} is not registered", map_id))
+            })?
+            .serialize(serializer)
+    }
+}
+
 impl Shape for SparseMap {
     fn box_shape_clone(&self) -> Box<dyn Shape> {
         Box::new((*self).clone())
@@ -341,9 +5021,11 @@ impl Shape for SparseMap {
 
 impl SubMapCollection for SparseMap {
     fn add_sub_map(&mut self, local_position: Position, target: MapId) {
+        // Sub-map positions are stored offset by this map's position, as its tiles are; see `translate`.
+        let stored_position = local_position + *self.position();
         let mut target_area = *MAPS.read()[target].read().area();
         let area = self.area_mut();
-        *target_area.position_mut() = *target_area.position() + local_position;
+        *target_area.position_mut() = *target_area.position() + stored_position;
         let right_pin = area.right();
         let bottom_pin = area.bottom();
         *area.position_mut().x_mut() = area.position().x().min(target_area.position().x());
@@ -351,7 +5033,7 @@ impl SubMapCollection for SparseMap {
         area.right_set(area.right().max(target_area.right()).max(right_pin));
         area.bottom_set(area.bottom().max(target_area.bottom()).max(bottom_pin));
 
-        self.sub_maps.push(SubMap::new(local_position, target))
+        self.sub_maps.push(SubMap::new(stored_position, target))
     }
 
     fn get_sub_map_at(&self, index: usize) -> Option<&SubMap> {
@@ -366,11 +5048,11 @@ impl SubMapCollection for SparseMap {
         self.sub_maps.len()
     }
 
-    fn sub_maps(&self) -> SubMaps {
+    fn sub_maps(&self) -> SubMaps<'_> {
         SubMaps::new(&self.sub_maps)
     }
 
-    fn sub_maps_mut(&mut self) -> SubMapsMut {
+    fn sub_maps_mut(&mut self) -> SubMapsMut<'_> {
         SubMapsMut::new(&mut self.sub_maps)
     }
 }
@@ -0,0 +1,134 @@
+// External includes.
+use super::{Map, MapId, SubMap, MAPS};
+
+// Standard includes.
+use std::cell::{Cell, RefCell};
+
+// Internal includes.
+
+thread_local! {
+    /// The `MapId`s of the maps whose sub-maps are currently being resolved on this thread, outermost first.
+    static RESOLVING: RefCell<Vec<MapId>> = const { RefCell::new(Vec::new()) };
+    /// The length which `RESOLVING` may not reach; sub-maps are not resolved beyond it.
+    static DEPTH_LIMIT: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// Pops the innermost `MapId` from `RESOLVING` when dropped, so that it is popped even if resolution unwinds.
+struct ResolvingGuard;
+
+impl Drop for ResolvingGuard {
+    fn drop(&mut self) {
+        RESOLVING.with(|resolving| resolving.borrow_mut().pop());
+    }
+}
+
+/// Restores `DEPTH_LIMIT` to the held value when dropped, so that it is restored even if `f` unwinds.
+struct DepthLimitGuard(usize);
+
+impl Drop for DepthLimitGuard {
+    fn drop(&mut self) {
+        DEPTH_LIMIT.with(|depth_limit| depth_limit.set(self.0));
+    }
+}
+
+/// Calls `f` with each of `sub_maps`, and a read lock on the map it targets, while resolving the tiles of the map `map_id`.
+///
+/// Sub-maps whose target is `map_id`, or any map whose sub-maps are already being resolved further up the chain, are skipped, so that cyclic sub-maps cannot recurse forever; as are all sub-maps, once the depth limit set by [`with_depth_limit`](fn.with_depth_limit.html) is reached. Sub-maps whose target is not in `MAPS` at all are skipped as well.
+pub(crate) fn for_each_resolved_sub_map(
+    map_id: MapId,
+    sub_maps: &[SubMap],
+    mut f: impl FnMut(&SubMap, &dyn Map),
+) {
+    if sub_maps.is_empty() {
+        return;
+    }
+
+    let entered = RESOLVING.with(|resolving| {
+        let mut resolving = resolving.borrow_mut();
+        if resolving.len() >= DEPTH_LIMIT.with(|depth_limit| depth_limit.get()) {
+            false
+        } else {
+            resolving.push(map_id);
+            true
+        }
+    });
+    if !entered {
+        return;
+    }
+    let _resolving_guard = ResolvingGuard;
+
+    let maps = MAPS.read();
+    for sub_map in sub_maps.iter() {
+        let is_ancestor = RESOLVING.with(|resolving| resolving.borrow().contains(&sub_map.value()));
+        if is_ancestor {
+            continue;
+        }
+
+        let map = match maps.get(sub_map.value()) {
+            Some(map) => map.read(),
+            None => continue,
+        };
+        f(sub_map, &**map);
+    }
+}
+
+/// Calls `f` with sub-maps resolved at most `max_depth` levels below the current one, returning its result.
+pub(crate) fn with_depth_limit<TOutput>(max_depth: usize, f: impl FnOnce() -> TOutput) -> TOutput {
+    let current_depth = RESOLVING.with(|resolving| resolving.borrow().len());
+    let old_depth_limit = DEPTH_LIMIT.with(|depth_limit| depth_limit.get());
+    let depth_limit = current_depth.saturating_add(max_depth).min(old_depth_limit);
+    let _depth_limit_guard = DepthLimitGuard(old_depth_limit);
+    DEPTH_LIMIT.with(|limit| limit.set(depth_limit));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::*;
+    use crate::SparseMap;
+
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn resolving_len() -> usize {
+        RESOLVING.with(|resolving| resolving.borrow().len())
+    }
+
+    #[test]
+    fn missing_sub_map_target_is_skipped() {
+        let sub_map_id = SparseMap::new();
+        let sub_maps = [
+            SubMap::new(Position::zero(), MapId::MAX),
+            SubMap::new(Position::zero(), sub_map_id),
+        ];
+
+        let mut visited = Vec::new();
+        for_each_resolved_sub_map(MapId::MAX, &sub_maps, |sub_map, _| {
+            visited.push(sub_map.value())
+        });
+        assert!(visited == vec![sub_map_id]);
+        assert!(resolving_len() == 0);
+    }
+
+    #[test]
+    fn thread_locals_are_restored_after_unwinding() {
+        let sub_map_id = SparseMap::new();
+        let sub_maps = [SubMap::new(Position::zero(), sub_map_id)];
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            with_depth_limit(1, || {
+                for_each_resolved_sub_map(MapId::MAX, &sub_maps, |_, _| panic!("resolution failed"))
+            })
+        }));
+        assert!(result.is_err());
+        assert!(resolving_len() == 0, "RESOLVING was not popped");
+        assert!(
+            DEPTH_LIMIT.with(|depth_limit| depth_limit.get()) == usize::MAX,
+            "DEPTH_LIMIT was not restored"
+        );
+
+        let mut visited = 0;
+        for_each_resolved_sub_map(MapId::MAX, &sub_maps, |_, _| visited += 1);
+        assert!(visited == 1);
+    }
+}
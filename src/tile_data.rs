@@ -0,0 +1,68 @@
+// External includes.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Standard includes.
+use std::collections::BTreeMap;
+
+// Internal includes.
+
+/// Data attached to a tile of a [`SparseMap`](struct.SparseMap.html), alongside its `TileType`, such as the damage dealt by a trap, or the brightness of a torch.
+///
+/// Each value is an `i64`, named by a string key, so that games can attach whatever data they need without a new type for every kind of tile. See [`SparseMap::set_tile_data`](struct.SparseMap.html#method.set_tile_data).
+///
+/// ```
+/// # use dungen_minion_rooms::*;
+/// let mut tile_data = TileData::new().with("damage", 5);
+/// assert!(tile_data.get("damage") == Some(5));
+/// assert!(tile_data.get("brightness") == None);
+///
+/// assert!(tile_data.set("damage", 8) == Some(5));
+/// assert!(tile_data.remove("damage") == Some(8));
+/// assert!(tile_data.is_empty());
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize), serde(transparent))]
+pub struct TileData {
+    values: BTreeMap<String, i64>,
+}
+
+impl TileData {
+    /// Creates a `TileData` with no values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value named by `key`, or `None` if it is not set.
+    pub fn get(&self, key: &str) -> Option<i64> {
+        self.values.get(key).copied()
+    }
+
+    /// Returns whether no values are set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns an iterator over every value set, and the key which names it, in order of key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i64)> {
+        self.values
+            .iter()
+            .map(|(key, value)| (key.as_str(), *value))
+    }
+
+    /// Removes the value named by `key`, and returns it, or `None` if it was not set.
+    pub fn remove(&mut self, key: &str) -> Option<i64> {
+        self.values.remove(key)
+    }
+
+    /// Sets the value named by `key`, and returns the value previously set, if any.
+    pub fn set(&mut self, key: &str, value: i64) -> Option<i64> {
+        self.values.insert(key.to_string(), value)
+    }
+
+    /// Sets the value named by `key`, as per [`set`](#method.set), and returns the `TileData`, for chaining.
+    pub fn with(mut self, key: &str, value: i64) -> Self {
+        self.set(key, value);
+        self
+    }
+}
@@ -0,0 +1,17 @@
+// External includes.
+use super::MapId;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Which map provided the tile resolved at a local `Position`, as returned by [`SparseMap::tile_source_at_local`](struct.SparseMap.html#method.tile_source_at_local).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TileSource {
+    /// The tile is stored directly in the map itself, or is its out-of-bounds tile.
+    SelfMap,
+    /// The tile is provided by the sub-map targeting the given `MapId`, or by one of its own sub-maps.
+    SubMap(MapId),
+    /// No tile is resolved at the `Position`.
+    None,
+}
@@ -0,0 +1,95 @@
+// External includes.
+use super::{Map, TileType};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// Renders a [`Map`](trait.Map.html) as ASCII text.
+///
+/// `ToAscii` is implemented for every `Map`, including the `Box<dyn Map>`s stored in `MAPS`.
+pub trait ToAscii: Map {
+    /// Renders the `Map` as a `String`, with one character per tile, and one line per row of its area.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`, and rendered as follows:
+    ///
+    /// * `TileType::Floor` => `'.'`
+    /// * `TileType::Wall` => `'#'`
+    /// * `TileType::Portal` => `'+'`
+    /// * `TileType::Void`, or no tile => `' '`
+    ///
+    /// Rows are separated by `'\n'`, with no trailing newline; a `Map` with an empty area renders as an empty `String`. Use [`to_ascii_with`](#method.to_ascii_with) to supply a different mapping.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// # let target = SparseMap::new();
+    /// let mut sparse_map = SparseMap::default();
+    /// assert!(sparse_map.to_ascii() == "");
+    ///
+    /// for y in 0..4 {
+    ///     for x in 0..5 {
+    ///         let tile_type = if x == 0 || y == 0 || x == 4 || y == 3 {
+    ///             TileType::Wall
+    ///         } else {
+    ///             TileType::Floor
+    ///         };
+    ///         sparse_map.tile_type_at_local_set(Position::new(x, y), tile_type);
+    ///     }
+    /// }
+    /// sparse_map.add_portal(Position::new(4, 1), CardinalDirection::East, Position::zero(), target);
+    /// sparse_map.tile_type_at_local_set(Position::new(2, 2), TileType::Void);
+    ///
+    /// assert!(sparse_map.to_ascii() == "#####\n#...+\n#. .#\n#####");
+    /// ```
+    fn to_ascii(&self) -> String {
+        self.to_ascii_with(&|tile_type| match tile_type {
+            Some(TileType::Floor) => '.',
+            Some(TileType::Wall) => '#',
+            Some(TileType::Portal) => '+',
+            Some(TileType::Void) | None => ' ',
+        })
+    }
+
+    /// Renders the `Map` as a `String`, with one character per tile, as provided by `f`, and one line per row of its area.
+    ///
+    /// Tiles are resolved through `tile_type_at_local`. Rows are separated by `'\n'`, with no trailing newline; a `Map` with an empty area renders as an empty `String`.
+    ///
+    /// ```
+    /// # use dungen_minion_rooms::geometry::*;
+    /// # use dungen_minion_rooms::*;
+    /// let map_id = SparseMap::new();
+    /// let maps = MAPS.read();
+    /// let mut map = maps[map_id].write();
+    /// map.tile_type_at_local_set(Position::new(0, 0), TileType::Wall);
+    /// map.tile_type_at_local_set(Position::new(1, 1), TileType::Floor);
+    ///
+    /// let ascii = map.to_ascii_with(&|tile_type| match tile_type {
+    ///     Some(TileType::Wall) => 'W',
+    ///     Some(_) => 'o',
+    ///     None => '?',
+    /// });
+    /// assert!(ascii == "W?\n?o");
+    /// ```
+    fn to_ascii_with(&self, f: &dyn Fn(Option<TileType>) -> char) -> String {
+        let mut output = String::new();
+        if self.width() == 0 {
+            return output;
+        }
+
+        for y in 0..self.height() as i32 {
+            if y > 0 {
+                output.push('\n');
+            }
+
+            for x in 0..self.width() as i32 {
+                output.push(f(self.tile_type_at_local(Position::new(x, y))));
+            }
+        }
+
+        output
+    }
+}
+
+impl<TMap> ToAscii for TMap where TMap: Map + ?Sized {}
@@ -0,0 +1,189 @@
+// External includes.
+use super::{
+    Map, MapId, Portal, PortalCollection, Portals, PortalsMut, SubMap, SubMapCollection, SubMaps,
+    SubMapsMut, TileType,
+};
+use crate::geometry::*;
+
+// Standard includes.
+
+// Internal includes.
+
+/// A [`Map`](trait.Map.html) placeholder which takes the place of a map removed with [`unregister_map`](fn.unregister_map.html).
+///
+/// Its `MapId` is `MapId::MAX`, which never matches the index it is stored at; all reads return `None`, and all writes are discarded.
+#[derive(Clone)]
+pub(crate) struct UnregisteredMap {
+    area: Area,
+}
+
+impl UnregisteredMap {
+    pub(crate) fn new() -> Self {
+        Self {
+            area: Area::new(Position::zero(), Size::zero()),
+        }
+    }
+}
+
+impl ContainsLocalPosition for UnregisteredMap {
+    fn contains_local_position(&self, _position: Position) -> Containment {
+        Containment::Disjoint
+    }
+}
+
+impl ContainsPosition for UnregisteredMap {}
+
+impl HasArea for UnregisteredMap {
+    fn area(&self) -> &Area {
+        &self.area
+    }
+
+    fn area_mut(&mut self) -> &mut Area {
+        &mut self.area
+    }
+}
+
+impl HasHeight for UnregisteredMap {
+    fn height(&self) -> Length {
+        self.size().height()
+    }
+
+    fn height_mut(&mut self) -> &mut Length {
+        self.size_mut().height_mut()
+    }
+}
+
+impl HasPosition for UnregisteredMap {
+    fn position(&self) -> &Position {
+        self.area.position()
+    }
+
+    fn position_mut(&mut self) -> &mut Position {
+        self.area.position_mut()
+    }
+}
+
+impl HasSize for UnregisteredMap {
+    fn size(&self) -> &Size {
+        self.area.size()
+    }
+
+    fn size_mut(&mut self) -> &mut Size {
+        self.area.size_mut()
+    }
+}
+
+impl HasWidth for UnregisteredMap {
+    fn width(&self) -> Length {
+        self.size().width()
+    }
+
+    fn width_mut(&mut self) -> &mut Length {
+        self.size_mut().width_mut()
+    }
+}
+
+impl IntersectsLocalPosition for UnregisteredMap {
+    fn intersects_local_position(&self, _position: Position) -> bool {
+        false
+    }
+}
+
+impl IntersectsPosition for UnregisteredMap {}
+
+impl Map for UnregisteredMap {
+    fn box_clone(&self) -> Box<dyn Map> {
+        Box::new((*self).clone())
+    }
+
+    fn map_id(&self) -> MapId {
+        MapId::MAX
+    }
+
+    fn rotate(&mut self, _rotation: CardinalRotation) {}
+
+    fn tile_type_at_local(&self, _pos: Position) -> Option<TileType> {
+        None
+    }
+
+    fn tile_type_at_local_mut(&mut self, _pos: Position) -> Option<&mut TileType> {
+        None
+    }
+
+    fn tile_type_at_local_set(&mut self, _pos: Position, _tile_type: TileType) -> Option<TileType> {
+        None
+    }
+
+    fn tile_type_at_local_sort_by<'a>(
+        &self,
+        _pos: Position,
+        _sort_best: &dyn Fn(&Option<TileType>, &Option<TileType>) -> std::cmp::Ordering,
+    ) -> Option<TileType> {
+        None
+    }
+}
+
+impl Placed for UnregisteredMap {}
+
+impl PlacedObject for UnregisteredMap {}
+
+impl PortalCollection for UnregisteredMap {
+    fn add_portal(
+        &mut self,
+        _local_position: Position,
+        _portal_to_map_facing: CardinalDirection,
+        _portal_to_map_position: Position,
+        _target: MapId,
+    ) {
+    }
+
+    fn get_portal_at(&self, _index: usize) -> Option<&Portal> {
+        None
+    }
+
+    fn get_portal_at_mut(&mut self, _index: usize) -> Option<&mut Portal> {
+        None
+    }
+
+    fn portal_count(&self) -> usize {
+        0
+    }
+
+    fn portals(&self) -> Portals<'_> {
+        Portals::new(&[])
+    }
+
+    fn portals_mut(&mut self) -> PortalsMut<'_> {
+        PortalsMut::new(&mut [])
+    }
+}
+
+impl Shape for UnregisteredMap {
+    fn box_shape_clone(&self) -> Box<dyn Shape> {
+        Box::new((*self).clone())
+    }
+}
+
+impl SubMapCollection for UnregisteredMap {
+    fn add_sub_map(&mut self, _local_position: Position, _target: MapId) {}
+
+    fn get_sub_map_at(&self, _index: usize) -> Option<&SubMap> {
+        None
+    }
+
+    fn get_sub_map_at_mut(&mut self, _index: usize) -> Option<&mut SubMap> {
+        None
+    }
+
+    fn sub_map_count(&self) -> usize {
+        0
+    }
+
+    fn sub_maps(&self) -> SubMaps<'_> {
+        SubMaps::new(&[])
+    }
+
+    fn sub_maps_mut(&mut self) -> SubMapsMut<'_> {
+        SubMapsMut::new(&mut [])
+    }
+}
@@ -0,0 +1,37 @@
+// External includes.
+
+// Standard includes.
+
+// Internal includes.
+
+/// A small, deterministic xorshift64* pseudo-random number generator; the same seed always produces the same sequence, on every platform. It is not suitable for cryptography.
+pub(crate) struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// Creates a generator from `seed`; every seed, including `0`, yields a distinct, usable sequence.
+    pub(crate) fn new(seed: u64) -> Self {
+        // Scrambled with splitmix64, as xorshift must not start from a zero state.
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// Returns a number in the range `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns the next number in the sequence.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}